@@ -0,0 +1,46 @@
+use tokio::fs::read_to_string;
+use tokio::io::{stdin, AsyncReadExt as _};
+
+use crate::{Error, Result};
+
+// An argument group read from a line of an argument file.
+#[derive(Debug)]
+pub(super) struct Line {
+    pub(super) number: usize,
+    pub(super) argv: Vec<String>,
+}
+
+// Splits each line into arguments at whitespace. Blank lines, and text after
+// `#`, are skipped.
+fn parse(s: &str) -> Vec<Line> {
+    s.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let line = line.split('#').next().unwrap_or_default();
+            let argv: Vec<_> = line.split_whitespace().map(String::from).collect();
+            if argv.is_empty() {
+                None
+            } else {
+                Some(Line {
+                    number: i + 1,
+                    argv,
+                })
+            }
+        })
+        .collect()
+}
+
+// Reads argument groups from a file, or from stdin when the path is `-`.
+pub(super) async fn read(path: &str) -> Result<Vec<Line>> {
+    log::trace!("argfile read start");
+    let s = if "-" == path {
+        let mut s = String::new();
+        stdin().read_to_string(&mut s).await.map_err(|e| Error::io(e, "stdin"))?;
+        s
+    } else {
+        read_to_string(path).await.map_err(|e| Error::io(e, path))?
+    };
+    let r = parse(&s);
+    log::trace!("argfile read done");
+    Ok(r)
+}
@@ -0,0 +1,349 @@
+use std::iter;
+use std::time::{Duration, Instant, SystemTime};
+
+use futures::stream::TryStreamExt as _;
+use tokio::fs::{read_dir, read_to_string};
+use tokio::time::sleep;
+
+use crate::app::profile::{self, Subcommands};
+use crate::app::{write_stdout, App, Arg, Parser};
+use crate::applet::Change;
+use crate::util::format::{render, Section};
+use crate::util::once;
+use crate::{Error, Result};
+
+pub(super) const DAEMON: &str = "daemon";
+
+const AC: &str = "ac";
+const BATTERY: &str = "battery";
+const PROFILE: &str = "profile";
+const INTERVAL: &str = "interval";
+const RECONCILE: &str = "reconcile";
+
+const INTERVAL_SHORT: char = 'i';
+const RECONCILE_SHORT: char = 'r';
+
+const DAEMON_ABOUT: &str = "Apply profiles, and keep their values applied";
+
+const AC_HELP: &str = "Profile applied on AC power";
+const BATTERY_HELP: &str = "Profile applied on battery power";
+const PROFILE_HELP: &str = "Profile applied on any power source";
+const INTERVAL_HELP: &str = "Set poll interval in seconds";
+const RECONCILE_HELP: &str = "Reapply drifted values every SECS seconds";
+
+const INTERVAL_DEFAULT: &str = "5";
+
+// The minimum gap between wall and monotonic time across a poll, for the
+// system to be considered resumed from suspend.
+const RESUME_GAP: Duration = Duration::from_secs(2);
+
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+#[rustfmt::skip]
+fn ac_help_long() -> String {
+"Profile applied at start on AC power, and on each
+change to AC power. A profile name from the search
+path, or a path containing /
+Example: --ac performance".to_string()
+}
+
+#[rustfmt::skip]
+fn battery_help_long() -> String {
+"Profile applied at start on battery power, and on
+each change to battery power. A profile name from
+the search path, or a path containing /
+Example: --battery ./powersave.toml".to_string()
+}
+
+#[rustfmt::skip]
+fn profile_help_long() -> String {
+"Profile applied on power sources without a profile
+given by --ac or --battery. A profile name from the
+search path, or a path containing /".to_string()
+}
+
+#[rustfmt::skip]
+fn interval_help_long() -> String {
+    format!(
+"Set the interval in seconds at which the power
+source, resume from suspend, and online cpus and
+gpus are polled, e.g. 0.5
+Default: {}",
+    INTERVAL_DEFAULT)
+}
+
+#[rustfmt::skip]
+fn reconcile_help_long() -> String {
+"Compare live values with the applied profile every
+SECS seconds, and reapply the profile if any differ.
+Values are also compared after resume from suspend,
+and when cpus or gpus come and go. Differing values
+are logged as warnings".to_string()
+}
+
+// Returns the daemon subcommand, as name, about and arguments.
+pub(super) fn subcommands() -> Vec<(&'static str, &'static str, Vec<Arg>)> {
+    let args = vec![
+        Arg {
+            name: AC.into(),
+            long: AC.into(),
+            value_name: "PROFILE".into(),
+            help: AC_HELP.into(),
+            help_long: ac_help_long().into(),
+            ..Default::default()
+        },
+        Arg {
+            name: BATTERY.into(),
+            long: BATTERY.into(),
+            value_name: "PROFILE".into(),
+            help: BATTERY_HELP.into(),
+            help_long: battery_help_long().into(),
+            ..Default::default()
+        },
+        Arg {
+            name: PROFILE.into(),
+            long: PROFILE.into(),
+            value_name: "PROFILE".into(),
+            help: PROFILE_HELP.into(),
+            help_long: profile_help_long().into(),
+            ..Default::default()
+        },
+        Arg {
+            name: INTERVAL.into(),
+            long: INTERVAL.into(),
+            short: INTERVAL_SHORT.into(),
+            value_name: "SECS".into(),
+            help: INTERVAL_HELP.into(),
+            help_long: interval_help_long().into(),
+            ..Default::default()
+        },
+        Arg {
+            name: RECONCILE.into(),
+            long: RECONCILE.into(),
+            short: RECONCILE_SHORT.into(),
+            value_name: "SECS".into(),
+            help: RECONCILE_HELP.into(),
+            help_long: reconcile_help_long().into(),
+            ..Default::default()
+        },
+    ];
+    vec![(DAEMON, DAEMON_ABOUT, args)]
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Source {
+    Ac,
+    Battery,
+}
+
+impl Source {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Ac => AC,
+            Self::Battery => BATTERY,
+        }
+    }
+}
+
+async fn read_attribute(dir: &std::path::Path, name: &str) -> String {
+    read_to_string(dir.join(name)).await.unwrap_or_default().trim().to_string()
+}
+
+// Returns the power source. Systems with an online mains or usb supply, or
+// without a system battery, are on AC power. Batteries of devices, e.g. mice,
+// are ignored.
+async fn power_source() -> Source {
+    let mut ac = false;
+    let mut battery = false;
+    if let Ok(mut entries) = read_dir(POWER_SUPPLY).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let dir = entry.path();
+            match read_attribute(&dir, "type").await.as_str() {
+                "Mains" | "USB" => ac |= "1" == read_attribute(&dir, "online").await,
+                "Battery" => battery |= "Device" != read_attribute(&dir, "scope").await,
+                _ => {},
+            }
+        }
+    }
+    if ac || !battery { Source::Ac } else { Source::Battery }
+}
+
+// Returns the ids of online cpus and of drm cards, which change on hotplug.
+async fn device_ids() -> (Vec<u64>, Vec<u64>) {
+    once::refresh().await;
+    let mut cpus: Vec<_> = syx::cpu::online_ids().try_collect().await.unwrap_or_default();
+    cpus.sort_unstable();
+    let cards = once::drm_cards().await.iter().map(|v| v.id()).collect();
+    (cpus, cards)
+}
+
+#[derive(Debug)]
+pub(super) struct Config {
+    ac: Option<String>,
+    battery: Option<String>,
+    profile: Option<String>,
+    interval: Duration,
+    reconcile: Option<Duration>,
+}
+
+impl Config {
+    // Returns the profile whose values are kept applied on a power source.
+    fn desired(&self, source: Source) -> Option<&str> {
+        let v = match source {
+            Source::Ac => self.ac.as_deref(),
+            Source::Battery => self.battery.as_deref(),
+        };
+        v.or(self.profile.as_deref())
+    }
+}
+
+// Profiles are given by name, or by a path containing `/`.
+async fn path(v: Option<&str>) -> Result<Option<String>> {
+    match v {
+        Some(v) if v.contains('/') => Ok(Some(v.to_string())),
+        Some(v) => profile::resolve(v).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+// Parses the arguments of `daemon`. Profiles are read once, so that errors
+// are reported before the daemon starts.
+pub(super) async fn command(p: Parser<'_>, subcmds: &Subcommands) -> Result<Config> {
+    log::trace!("daemon command start");
+    let ac = path(p.str(AC)).await?;
+    let battery = path(p.str(BATTERY)).await?;
+    let profile = path(p.str(PROFILE)).await?;
+    if ac.is_none() && battery.is_none() && profile.is_none() {
+        return Err(Error::parse_value(
+            "daemon requires --ac, --battery or --profile",
+        ));
+    }
+    for path in ac.iter().chain(&battery).chain(&profile) {
+        profile::read(path, subcmds).await?;
+    }
+    let interval = match p.seconds(INTERVAL)? {
+        Some(v) => v,
+        None => Duration::from_secs(INTERVAL_DEFAULT.parse().expect("interval default")),
+    };
+    let r = Config {
+        ac,
+        battery,
+        profile,
+        interval: interval.max(Duration::from_millis(1)),
+        reconcile: p.seconds(RECONCILE)?,
+    };
+    log::trace!("daemon command done");
+    Ok(r)
+}
+
+impl App {
+    // Applies the profile of the power source at start, and on each change of
+    // power source. Live values are compared with the profile periodically,
+    // after resume and on hotplug, and the profile is reapplied if they
+    // drifted. Errors are logged, and do not stop the daemon.
+    pub(super) async fn run_daemon(&self, config: Config) {
+        log::trace!("app daemon start");
+        let mut source = None;
+        let mut devices = device_ids().await;
+        let mut reconciled = Instant::now();
+        let mut resumed = false;
+        // The changes of the profile last applied, with the values read back.
+        let mut applied = vec![];
+        loop {
+            let current = power_source().await;
+            let current_devices = device_ids().await;
+            if Some(current) != source {
+                source = Some(current);
+                log::info!("daemon: power source {}", current.name());
+                applied = vec![];
+                if let Some(path) = config.desired(current) {
+                    match self.apply_profile(current, path, true).await {
+                        Ok(v) => applied = v,
+                        Err(e) => log::error!("daemon: {}", e),
+                    }
+                }
+                reconciled = Instant::now();
+            } else if let Some(path) = config.desired(current) {
+                let reason = if resumed {
+                    Some("resume")
+                } else if current_devices != devices {
+                    Some("hotplug")
+                } else if config.reconcile.map(|v| reconciled.elapsed() >= v).unwrap_or(false) {
+                    Some("interval")
+                } else {
+                    None
+                };
+                if let Some(reason) = reason {
+                    log::info!("daemon: reconcile after {}", reason);
+                    if let Err(e) = self.reconcile(current, path, &mut applied).await {
+                        log::error!("daemon: {}", e);
+                    }
+                    reconciled = Instant::now();
+                }
+            }
+            devices = current_devices;
+            let (wall, monotonic) = (SystemTime::now(), Instant::now());
+            sleep(config.interval).await;
+            // Monotonic time stops while suspended, and wall time does not.
+            let wall = wall.elapsed().unwrap_or_default();
+            resumed = wall.saturating_sub(monotonic.elapsed()) >= RESUME_GAP;
+        }
+    }
+
+    // Makes and joins the runners of a profile in a new app, as `knobs apply`.
+    // Unless `record`, the values replaced are not stored in the undo history.
+    async fn run_profile(&self, path: &str, dry_run: bool, record: bool) -> Result<App> {
+        // Devices may come and go while the daemon runs.
+        once::refresh().await;
+        let argv = [self.argv0.clone(), profile::APPLY.into(), path.into()];
+        let mut app = App::new(argv);
+        if dry_run {
+            app.journal.set_dry_run();
+        }
+        if !record {
+            app.journal.set_unrecorded();
+        }
+        let r = async {
+            app.make_subcommand_runners().await?;
+            app.join_runners().await
+        }
+        .await;
+        r.map(|_| app)
+    }
+
+    async fn apply_profile(&self, source: Source, path: &str, record: bool) -> Result<Vec<Change>> {
+        let app = self.run_profile(path, self.journal.is_dry_run(), record).await?;
+        if !self.quiet {
+            let status = Section::Status("power", source.name().into());
+            let sections: Vec<_> = iter::once(status)
+                .chain(app.reports.iter().cloned())
+                .chain(app.journal.format())
+                .collect();
+            write_stdout(&render(self.format, &sections)).await;
+        }
+        Ok(app.journal.changes())
+    }
+
+    // Compares live values with a profile through a dry run, and reapplies
+    // the profile if any differ from the values read back when it was last
+    // applied. Reapplies are not undone on their own, so that they do not push
+    // other invocations out of the undo history.
+    async fn reconcile(&self, source: Source, path: &str, applied: &mut Vec<Change>) -> Result<()> {
+        let app = self.run_profile(path, true, false).await?;
+        let drift = app.journal.drift(applied);
+        for (v, expected) in &drift {
+            let old = v.old.as_ref().map(ToString::to_string).unwrap_or_default();
+            log::warn!(
+                "daemon: drift {} {}: {}, expected {}",
+                v.knob.device(),
+                v.knob.attribute(),
+                old,
+                expected
+            );
+        }
+        if !drift.is_empty() {
+            *applied = self.apply_profile(source, path, false).await?;
+        }
+        Ok(())
+    }
+}
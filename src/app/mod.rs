@@ -1,23 +1,100 @@
+mod argfile;
+mod daemon;
 mod parser;
+mod profile;
 
-use std::collections::HashSet;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
 use std::iter;
+use std::time::Duration;
 
 use clap::ErrorKind as ClapErrorKind;
-use futures::future::join_all;
+use futures::future::FutureExt as _;
 use tokio::io::{stderr, stdout, AsyncWriteExt as _, BufWriter};
+use tokio::time::sleep;
 
-pub(crate) use crate::app::parser::{I915Driver, NvmlDriver, Parser};
-use crate::applet::{self, Applet, Runner};
-use crate::util::counter;
+pub(crate) use crate::app::parser::{resolve_level, I915Driver, Level, NvmlDriver, Parser};
+use crate::applet::{self, Applet, Id, Journal, Runner};
 use crate::util::env::var_name;
+use crate::util::format::{render, Format, Section};
+use crate::util::{counter, once};
 use crate::{Error, Result};
 
 pub(crate) const NAME: &str = "knobs";
 
 const QUIET: &str = "quiet";
+const ALL: &str = "all";
+const FORMAT: &str = "format";
+const SHOW: &str = "show";
+const WATCH: &str = "watch";
+const DRY_RUN: &str = "dry-run";
+const FILE: &str = "file";
+
 const QUIET_SHORT: char = 'q';
+const ALL_SHORT: char = 'a';
+const FILE_SHORT: char = 'f';
+
 const QUIET_HELP: &str = "Do not print tables";
+const ALL_HELP: &str = "Print tables for all devices";
+const FORMAT_HELP: &str = "Set table output format";
+const SHOW_HELP: &str = "Print only the named tables and columns";
+const WATCH_HELP: &str = "Print tables repeatedly, every 2 seconds by default";
+const DRY_RUN_HELP: &str = "Print planned writes instead of writing";
+const FILE_HELP: &str = "Read argument groups from a file";
+
+const WATCH_DEFAULT: &str = "2";
+
+// Moves the cursor home and clears the terminal.
+const CLEAR: &str = "\x1b[H\x1b[2J";
+
+#[rustfmt::skip]
+fn all_help_long() -> String {
+"Print tables for all devices, instead of
+only the devices targeted by argument groups".to_string()
+}
+
+#[rustfmt::skip]
+fn format_help_long() -> String {
+"Set table output format
+Format syntax: text json csv tsv prometheus".to_string()
+}
+
+#[rustfmt::skip]
+fn show_help_long() -> String {
+"Print only the named tables and columns, as a
+comma-separated list. Naming a column prints its
+table with only that column and the id column.
+Tables may be named without the applet prefix.
+Example: --show governors,epp
+Example: --show cpu,governor,cur".to_string()
+}
+
+#[rustfmt::skip]
+fn watch_help_long() -> String {
+    format!(
+"Print tables repeatedly until interrupted, waiting
+SECS seconds between updates. Text tables are
+redrawn in place.
+Default: {}",
+    WATCH_DEFAULT)
+}
+
+#[rustfmt::skip]
+fn dry_run_help_long() -> String {
+"Validate all argument groups, then print the planned
+writes with the current and new values, instead of
+writing. Subcommands which do not write values, e.g.
+log or save, are not run".to_string()
+}
+
+#[rustfmt::skip]
+fn file_help_long() -> String {
+"Read argument groups from a file, one per line, or
+from stdin when FILE is -. Blank lines and text after
+# are ignored. The groups follow the group containing
+-f/--file, and are validated before any are run
+Example: cpu -c 0..3 -g performance".to_string()
+}
 
 #[derive(Debug, Default)]
 pub(crate) struct Arg {
@@ -25,12 +102,14 @@ pub(crate) struct Arg {
     pub(crate) long: Option<&'static str>,
     pub(crate) short: Option<char>,
     pub(crate) value_name: Option<&'static str>,
+    pub(crate) default_missing_value: Option<&'static str>,
     pub(crate) help: Option<&'static str>,
     pub(crate) help_long: Option<String>,
     pub(crate) required: Option<bool>,
     pub(crate) requires: Option<Vec<&'static str>>,
     pub(crate) conflicts: Option<Vec<&'static str>>,
     pub(crate) raw: Option<bool>,
+    pub(crate) allow_hyphen_values: Option<bool>,
 }
 
 impl<'a> From<&'a Arg> for clap::Arg<'a> {
@@ -46,6 +125,9 @@ impl<'a> From<&'a Arg> for clap::Arg<'a> {
         if let Some(value_name) = v.value_name {
             a = a.takes_value(true).value_name(value_name);
         }
+        if let Some(default_missing_value) = v.default_missing_value {
+            a = a.min_values(0).default_missing_value(default_missing_value);
+        }
         if let Some(help) = v.help {
             a = a.help(help);
         }
@@ -68,6 +150,9 @@ impl<'a> From<&'a Arg> for clap::Arg<'a> {
         if let Some(raw) = v.raw {
             a = a.raw(raw);
         }
+        if let Some(allow_hyphen_values) = v.allow_hyphen_values {
+            a = a.allow_hyphen_values(allow_hyphen_values);
+        }
         a
     }
 }
@@ -84,13 +169,198 @@ where
 }
 
 fn make_app_args() -> Vec<Arg> {
-    vec![Arg {
-        name: QUIET.into(),
-        long: QUIET.into(),
-        short: QUIET_SHORT.into(),
-        help: QUIET_HELP.into(),
-        ..Default::default()
-    }]
+    vec![
+        Arg {
+            name: QUIET.into(),
+            long: QUIET.into(),
+            short: QUIET_SHORT.into(),
+            help: QUIET_HELP.into(),
+            ..Default::default()
+        },
+        Arg {
+            name: ALL.into(),
+            long: ALL.into(),
+            short: ALL_SHORT.into(),
+            help: ALL_HELP.into(),
+            help_long: all_help_long().into(),
+            ..Default::default()
+        },
+        Arg {
+            name: FORMAT.into(),
+            long: FORMAT.into(),
+            value_name: "FMT".into(),
+            help: FORMAT_HELP.into(),
+            help_long: format_help_long().into(),
+            ..Default::default()
+        },
+        Arg {
+            name: SHOW.into(),
+            long: SHOW.into(),
+            value_name: "LIST".into(),
+            help: SHOW_HELP.into(),
+            help_long: show_help_long().into(),
+            ..Default::default()
+        },
+        Arg {
+            name: WATCH.into(),
+            long: WATCH.into(),
+            value_name: "SECS".into(),
+            default_missing_value: WATCH_DEFAULT.into(),
+            help: WATCH_HELP.into(),
+            help_long: watch_help_long().into(),
+            ..Default::default()
+        },
+        Arg {
+            name: DRY_RUN.into(),
+            long: DRY_RUN.into(),
+            help: DRY_RUN_HELP.into(),
+            help_long: dry_run_help_long().into(),
+            ..Default::default()
+        },
+        Arg {
+            name: FILE.into(),
+            long: FILE.into(),
+            short: FILE_SHORT.into(),
+            value_name: "FILE".into(),
+            help: FILE_HELP.into(),
+            help_long: file_help_long().into(),
+            ..Default::default()
+        },
+    ]
+}
+
+// Flags which require a raw argument, e.g. `--measure`, which requires the
+// command given after `--`.
+#[derive(Clone, Debug, Default)]
+struct RawFlags {
+    long: Vec<String>,
+    short: Vec<char>,
+    // Short flags of the applet and app which take a value.
+    valued: Vec<char>,
+}
+
+impl RawFlags {
+    // True if an argument is a raw flag, alone or in a cluster of short flags
+    // such as `-qm`. As in clap, a short flag which takes a value ends its
+    // cluster, e.g. `-l15`.
+    fn matches(&self, v: &str) -> bool {
+        if let Some(v) = v.strip_prefix("--") {
+            return self.long.iter().any(|f| f == v.split('=').next().unwrap_or(v));
+        }
+        if let Some(cluster) = v.strip_prefix('-') {
+            for c in cluster.chars() {
+                if self.short.contains(&c) {
+                    return true;
+                }
+                if self.valued.contains(&c) {
+                    break;
+                }
+            }
+        }
+        false
+    }
+}
+
+fn raw_flags(args: &[Arg]) -> RawFlags {
+    let raw: Vec<_> = args.iter().filter(|a| a.raw == Some(true)).filter_map(|a| a.name).collect();
+    let flags: Vec<_> = args
+        .iter()
+        .filter(|a| a.requires.iter().flatten().any(|v| raw.contains(v)))
+        .collect();
+    let valued = args
+        .iter()
+        .chain(&make_app_args())
+        .filter(|a| a.value_name.is_some())
+        .filter_map(|a| a.short)
+        .collect();
+    RawFlags {
+        long: flags.iter().filter_map(|a| a.long).map(Into::into).collect(),
+        short: flags.iter().filter_map(|a| a.short).collect(),
+        valued,
+    }
+}
+
+// Splits argv into argument groups delimited by `--`. A group containing one
+// of its raw flags keeps the rest of argv, including the `--`.
+fn split_groups<F>(argv: &[String], raw_flags: F) -> Vec<&[String]>
+where
+    F: Fn(&[String]) -> RawFlags,
+{
+    let mut groups = vec![];
+    let mut start = 0;
+    for (i, v) in argv.iter().enumerate() {
+        if "--" == v {
+            let group = &argv[start..i];
+            let raw = raw_flags(group);
+            if group.iter().any(|v| raw.matches(v)) {
+                groups.push(&argv[start..]);
+                return groups;
+            }
+            groups.push(group);
+            start = i + 1;
+        }
+    }
+    groups.push(&argv[start..]);
+    groups
+}
+
+// Adds the device ids targeted by an argument group to the ids formatted for
+// its subcommand. `None` formats all devices.
+fn select(
+    format_subcmds: &mut HashMap<&'static str, Option<Vec<Id>>>,
+    subcmd: &'static str,
+    ids: Option<Vec<Id>>,
+) {
+    match format_subcmds.entry(subcmd) {
+        Entry::Occupied(mut e) => {
+            let v = e.get_mut();
+            if let (Some(v), Some(ids)) = (v.as_mut(), ids.as_ref()) {
+                v.extend(ids);
+            } else {
+                *v = None;
+            }
+        },
+        Entry::Vacant(e) => {
+            e.insert(ids);
+        },
+    }
+}
+
+// Argument groups to be parsed, with the file and line number of those read
+// from argument files.
+type Groups = VecDeque<(Vec<String>, Option<(String, usize)>)>;
+
+// Queues the argument groups of an argument file after the current group.
+async fn read_argfile(
+    groups: &mut Groups,
+    path: &str,
+    line: Option<&(String, usize)>,
+) -> Result<()> {
+    if line.is_some() {
+        return Err(Error::parse_value(
+            "argument files cannot read argument files",
+        ));
+    }
+    for v in argfile::read(path).await?.into_iter().rev() {
+        groups.push_front((v.argv, Some((path.to_string(), v.number))));
+    }
+    Ok(())
+}
+
+// Returns the error of an argument group, with its line if read from a file.
+fn group_error(lines: &HashMap<usize, (String, usize)>, error: Error, group: usize) -> Error {
+    let error = match lines.get(&group) {
+        Some((path, line)) => Error::line(error, path, *line),
+        None => error,
+    };
+    Error::group(error, group + 1)
+}
+
+// Skips the runner in dry-run mode, unless its applet supports dry runs. The
+// mode is checked when the runner is polled, as it may be set by a later group.
+fn dry_run_runner(runner: Runner, journal: Journal, supported: bool) -> Runner {
+    async move { if supported || !journal.is_dry_run() { runner.await } else { Ok(vec![]) } }
+        .boxed()
 }
 
 struct App {
@@ -98,8 +368,16 @@ struct App {
     argv: Vec<String>,
     applets: Vec<Box<dyn Applet>>,
     quiet: bool,
+    all: bool,
+    format: Format,
+    show: Option<Vec<String>>,
+    watch: Option<Duration>,
     runners: Vec<(usize, Runner)>,
-    format_subcmds: HashSet<&'static str>,
+    lines: HashMap<usize, (String, usize)>,
+    daemon: Option<daemon::Config>,
+    reports: Vec<Section>,
+    journal: Journal,
+    format_subcmds: HashMap<&'static str, Option<Vec<Id>>>,
 }
 
 impl App {
@@ -117,8 +395,16 @@ impl App {
             argv: argv.collect(),
             applets: applet::all(),
             quiet: false,
+            all: false,
+            format: Format::default(),
+            show: None,
+            watch: None,
             runners: vec![],
-            format_subcmds: HashSet::new(),
+            lines: HashMap::new(),
+            daemon: None,
+            reports: vec![],
+            journal: Journal::default(),
+            format_subcmds: HashMap::new(),
         };
         log::trace!("app init done");
         r
@@ -132,8 +418,25 @@ impl App {
             self.make_subcommand_runners().await?;
         }
         self.join_runners().await?;
+        if let Some(config) = self.daemon.take() {
+            self.run_daemon(config).await;
+        }
         if !self.quiet {
-            self.format().await;
+            if let Some(interval) = self.watch {
+                self.watch(interval).await;
+            } else {
+                self.format().await;
+            }
+        } else {
+            // Reports, and planned writes, are printed even when quiet.
+            let planned = self.journal.format().filter(|_| self.journal.is_dry_run());
+            let sections: Vec<_> = self.reports.iter().cloned().chain(planned).collect();
+            if !sections.is_empty() {
+                write_stdout(&render(self.format, &sections)).await;
+            }
+        }
+        if let Some(status) = applet::rapl_measure_failed() {
+            return Err(Error::Command(status));
         }
         log::trace!("app run done");
         Ok(())
@@ -157,29 +460,56 @@ impl App {
             let applet_args_data = applet.args();
             let app_args = app_args_data.iter().map(clap::Arg::from);
             let applet_args = applet_args_data.iter().map(clap::Arg::from).chain(app_args);
-            let argvs = self.argv.split(|v| "--" == v).map(|argv| {
-                let argv = argv.iter().map(|v| v.as_str());
-                iter::once(argv0).chain(argv)
-            });
-            for (i, argv) in argvs.enumerate() {
+            let applet_raw_flags = raw_flags(&applet_args_data);
+            let groups = split_groups(&self.argv, |_| applet_raw_flags.clone());
+            let mut groups: Groups = groups.into_iter().map(|v| (v.to_vec(), None)).collect();
+            let mut i = 0;
+            while let Some((argv, line)) = groups.pop_front() {
+                if let Some(line) = line.clone() {
+                    self.lines.insert(i, line);
+                }
                 async {
                     let app = make_clap_app(argv0).about(applet.about()).args(applet_args.clone());
+                    let argv = iter::once(argv0.to_string()).chain(argv);
                     let matches = app.try_get_matches_from(argv)?;
                     let parser = Parser::from(&matches);
                     if parser.flag(QUIET).is_some() {
                         self.quiet = true;
                     }
-                    let runner = applet.run(parser).await?;
+                    if parser.flag(ALL).is_some() {
+                        self.all = true;
+                    }
+                    if let Some(format) = parser.format(FORMAT)? {
+                        self.format = format;
+                    }
+                    if let Some(show) = parser.list(SHOW) {
+                        self.show = Some(show);
+                    }
+                    if let Some(watch) = parser.seconds(WATCH)? {
+                        self.watch = Some(watch);
+                    }
+                    if parser.flag(DRY_RUN).is_some() {
+                        self.journal.set_dry_run();
+                    }
+                    if let Some(path) = parser.str(FILE) {
+                        read_argfile(&mut groups, path, line.as_ref()).await?;
+                    }
+                    let (runner, ids) = applet.run(parser, self.journal.clone()).await?;
+                    let runner = dry_run_runner(runner, self.journal.clone(), applet.dry_run());
                     self.runners.push((i, runner));
+                    select(&mut self.format_subcmds, applet.subcommand(), ids);
                     Ok(())
                 }
                 .await
-                .map_err(|e| Error::group(e, i + 1))?;
+                .map_err(|e| group_error(&self.lines, e, i))?;
+                i += 1;
             }
             log::trace!("app make binary runners done");
         }
-        if !self.quiet {
-            self.format_subcmds.insert(applet.subcommand());
+        if self.quiet {
+            self.format_subcmds.clear();
+        } else {
+            self.format_subcmds.entry(applet.subcommand()).or_insert(None);
         }
         Ok(())
     }
@@ -187,102 +517,235 @@ impl App {
     async fn make_subcommand_runners(&mut self) -> Result<()> {
         if !self.argv.is_empty() {
             log::trace!("app make subcommand runners start");
-            let argv0 = self.argv0.as_str();
+            // Owned, as runners are made through `&mut self`.
+            let (argv0, argv) = (self.argv0.clone(), self.argv.clone());
+            let argv0 = argv0.as_str();
             let app_args_data = make_app_args();
-            let applet_args_data: Vec<_> =
-                self.applets.iter().map(|a| (a.subcommand(), a.about(), a.args())).collect();
+            let applet_args_data: Vec<_> = self
+                .applets
+                .iter()
+                .map(|a| (a.subcommand(), a.about(), a.args()))
+                .chain(profile::subcommands())
+                .chain(daemon::subcommands())
+                .collect();
             let app_args = app_args_data.iter().map(clap::Arg::from);
             let applet_args = applet_args_data
                 .iter()
                 .map(|(n, a, args)| (*n, *a, args.iter().map(clap::Arg::from).collect::<Vec<_>>()));
-            let argvs = self.argv.split(|v| "--" == v).map(|argv| {
-                let argv = argv.iter().map(|v| v.as_str());
-                iter::once(argv0).chain(argv)
+            let applet_raw_flags: Vec<_> =
+                applet_args_data.iter().map(|(n, _, args)| (*n, raw_flags(args))).collect();
+            let groups = split_groups(&argv, |group| {
+                group
+                    .iter()
+                    .find_map(|v| applet_raw_flags.iter().find(|(n, _)| n == v))
+                    .map(|(_, flags)| flags.clone())
+                    .unwrap_or_default()
             });
-            for (i, argv) in argvs.enumerate() {
+            let mut groups: Groups = groups.into_iter().map(|v| (v.to_vec(), None)).collect();
+            let clap_app = || {
+                applet_args.clone().fold(
+                    make_clap_app(argv0).args(app_args.clone()),
+                    |clap_app, (name, about, args)| {
+                        let subcmd = make_clap_app(name).about(about).args(args);
+                        clap_app.subcommand(subcmd)
+                    },
+                )
+            };
+            let mut i = 0;
+            while let Some((argv, line)) = groups.pop_front() {
+                if let Some(line) = line.clone() {
+                    self.lines.insert(i, line);
+                }
                 async {
-                    let matches = applet_args
-                        .clone()
-                        .fold(
-                            make_clap_app(argv0).args(app_args.clone()),
-                            |clap_app, (name, about, args)| {
-                                let subcmd = make_clap_app(name).about(about).args(args);
-                                clap_app.subcommand(subcmd)
-                            },
-                        )
-                        .try_get_matches_from(argv)?;
-                    if Parser::from(&matches).flag(QUIET).is_some() {
+                    let argv = iter::once(argv0.to_string()).chain(argv);
+                    let matches = clap_app().try_get_matches_from(argv)?;
+                    let parser = Parser::from(&matches);
+                    if parser.flag(QUIET).is_some() {
                         self.quiet = true;
                         self.format_subcmds.clear();
                     }
-                    if let Some((subcmd, subcmd_matches)) = matches.subcommand() {
-                        let applet = self
-                            .applets
-                            .iter()
-                            .find(|a| subcmd == a.subcommand())
-                            .expect("applet for subcommand");
-                        let parser = Parser::from(subcmd_matches);
-                        let runner = applet.run(parser).await?;
-                        self.runners.push((i, runner));
-                        if !self.quiet {
-                            self.format_subcmds.insert(applet.subcommand());
-                        }
+                    if parser.flag(ALL).is_some() {
+                        self.all = true;
+                    }
+                    if let Some(format) = parser.format(FORMAT)? {
+                        self.format = format;
+                    }
+                    if let Some(show) = parser.list(SHOW) {
+                        self.show = Some(show);
+                    }
+                    if let Some(watch) = parser.seconds(WATCH)? {
+                        self.watch = Some(watch);
+                    }
+                    if parser.flag(DRY_RUN).is_some() {
+                        self.journal.set_dry_run();
+                    }
+                    if let Some(path) = parser.str(FILE) {
+                        read_argfile(&mut groups, path, line.as_ref()).await?;
+                    }
+                    match matches.subcommand() {
+                        Some((subcmd @ (profile::APPLY | profile::PROFILE), subcmd_matches)) => {
+                            let parser = Parser::from(subcmd_matches);
+                            let path = match profile::command(subcmd, parser, &applet_args_data)
+                                .await?
+                            {
+                                profile::Command::Apply(path) => path,
+                                profile::Command::Report(reports) => {
+                                    self.reports.extend(reports);
+                                    // Formats no applets, unless other
+                                    // argument groups target them.
+                                    if !self.quiet {
+                                        select(&mut self.format_subcmds, profile::PROFILE, None);
+                                    }
+                                    return Ok(());
+                                },
+                            };
+                            let groups = profile::read(&path, &applet_args_data).await?;
+                            for group in groups {
+                                async {
+                                    let argv = iter::once(argv0.to_string()).chain(group.argv);
+                                    let matches = clap_app().try_get_matches_from(argv)?;
+                                    if let Some((subcmd, subcmd_matches)) = matches.subcommand() {
+                                        self.make_runner(i, subcmd, subcmd_matches).await?;
+                                    }
+                                    Ok(())
+                                }
+                                .await
+                                .map_err(|e| {
+                                    Error::profile(e, format!("{}: {}", path, group.name))
+                                })?;
+                            }
+                        },
+                        Some((daemon::DAEMON, subcmd_matches)) => {
+                            let parser = Parser::from(subcmd_matches);
+                            self.daemon = Some(daemon::command(parser, &applet_args_data).await?);
+                        },
+                        Some((subcmd, subcmd_matches)) => {
+                            self.make_runner(i, subcmd, subcmd_matches).await?;
+                        },
+                        None => {},
                     }
                     Ok(())
                 }
                 .await
-                .map_err(|e| Error::group(e, i + 1))?;
+                .map_err(|e| group_error(&self.lines, e, i))?;
+                i += 1;
             }
             log::trace!("app make subcommand runners done");
         }
         Ok(())
     }
 
+    async fn make_runner(
+        &mut self,
+        group: usize,
+        subcmd: &str,
+        matches: &clap::ArgMatches,
+    ) -> Result<()> {
+        let applet = self
+            .applets
+            .iter()
+            .find(|a| subcmd == a.subcommand())
+            .expect("applet for subcommand");
+        let parser = Parser::from(matches);
+        let (runner, ids) = applet.run(parser, self.journal.clone()).await?;
+        let runner = dry_run_runner(runner, self.journal.clone(), applet.dry_run());
+        self.runners.push((group, runner));
+        if !self.quiet {
+            select(&mut self.format_subcmds, applet.subcommand(), ids);
+        }
+        Ok(())
+    }
+
+    // Joins the runners, then stores the values they replaced in the undo
+    // history, which leaves each invocation as one entry. Values restored by a
+    // rollback are not stored.
     async fn join_runners(&mut self) -> Result<()> {
         log::trace!("app join runners start");
-        for (i, runner) in self.runners.drain(..) {
-            runner.await.map_err(|e| Error::group(e, i + 1))?;
+        let mut r = Ok(());
+        for (i, runner) in std::mem::take(&mut self.runners) {
+            match runner.await {
+                Ok(reports) => self.reports.extend(reports),
+                Err(e) => {
+                    let e = group_error(&self.lines, e, i);
+                    r = Err(self.rollback(e).await);
+                    break;
+                },
+            }
         }
+        let stored = self.journal.store_history().await;
         log::trace!("app join runners done");
-        Ok(())
+        match (r, stored) {
+            (Err(e), Err(stored)) => {
+                log::error!("storing undo history failed: {}", stored);
+                Err(e)
+            },
+            (r, stored) => r.and(stored),
+        }
     }
 
-    async fn format(&mut self) {
-        log::trace!("app format start");
-        let ctors = self.applets.iter().filter_map(|a| {
-            if self.format_subcmds.is_empty() || self.format_subcmds.contains(a.subcommand()) {
-                Some(a.format())
+    // Restores the values written before a runner failed, so that no argument
+    // group is left partly applied.
+    async fn rollback(&self, error: Error) -> Error {
+        if self.journal.changes().is_empty() || self.journal.is_dry_run() {
+            return error;
+        }
+        match self.journal.rollback().await {
+            Ok(()) => Error::rolled_back(error),
+            Err(e) => Error::rollback(error, e),
+        }
+    }
+
+    async fn render(&self) -> Option<String> {
+        let applets = self.applets.iter().filter_map(|a| {
+            let ids = if self.format_subcmds.is_empty() {
+                Some(None)
             } else {
-                None
-            }
+                self.format_subcmds.get(a.subcommand()).cloned()
+            }?;
+            let ids = if self.all { None } else { ids };
+            Some((a.as_ref(), ids))
         });
-        let futs = join_all(ctors).await.into_iter();
-        let tasks = futs.filter_map(|futs| {
-            if futs.is_empty() { None } else { Some(tokio::spawn(join_all(futs))) }
-        });
-        let outputs: Vec<_> = join_all(tasks)
-            .await
-            .into_iter()
-            .map(|r| r.expect("app format future"))
-            .flatten()
-            .flatten()
+        let mut sections = applet::format(applets).await;
+        if let Some(names) = self.show.as_deref() {
+            sections = sections.into_iter().filter_map(|v| v.show(names)).collect();
+        }
+        let sections: Vec<_> = self
+            .reports
+            .iter()
+            .cloned()
+            .chain(self.journal.format())
+            .chain(sections)
             .collect();
-        self.format_subcmds.clear();
-        if !outputs.is_empty() {
-            let mut stdout = BufWriter::with_capacity(4 * 1024, stdout());
-            let mut ok = false;
-            for output in outputs {
-                if ok {
-                    stdout.write_all("\n".as_bytes()).await.unwrap();
-                } else {
-                    ok = true;
-                }
-                stdout.write_all(output.as_bytes()).await.unwrap();
-            }
-            stdout.flush().await.unwrap();
+        if sections.is_empty() { None } else { Some(render(self.format, &sections)) }
+    }
+
+    async fn format(&self) {
+        log::trace!("app format start");
+        if let Some(output) = self.render().await {
+            write_stdout(&output).await;
         }
         log::trace!("app format done");
     }
+
+    async fn watch(&self, interval: Duration) {
+        log::trace!("app watch start");
+        loop {
+            // Devices may come and go between updates.
+            once::refresh().await;
+            let mut output = self.render().await.unwrap_or_default();
+            if self.format == Format::Text {
+                output.insert_str(0, CLEAR);
+            }
+            write_stdout(&output).await;
+            sleep(interval).await;
+        }
+    }
+}
+
+async fn write_stdout(output: &str) {
+    let mut stdout = BufWriter::with_capacity(4 * 1024, stdout());
+    stdout.write_all(output.as_bytes()).await.unwrap();
+    stdout.flush().await.unwrap();
 }
 
 fn config_logging() {
@@ -327,6 +790,19 @@ async fn try_run_with_args(argv: impl IntoIterator<Item = String>) -> Result<()>
     App::new(argv).run().await
 }
 
+// A failed measured command exits with its own code, or 128 plus the number of
+// the signal which killed it, as shells do.
+fn exit_code(e: &Error) -> i32 {
+    use std::os::unix::process::ExitStatusExt as _;
+
+    match e {
+        Error::Command(status) => {
+            status.code().or_else(|| status.signal().map(|v| 128 + v)).unwrap_or(2)
+        },
+        _ => 2,
+    }
+}
+
 async fn run_with_args(argv: impl IntoIterator<Item = String>) {
     if let Err(e) = try_run_with_args(argv).await {
         match e {
@@ -352,7 +828,7 @@ async fn run_with_args(argv: impl IntoIterator<Item = String>) {
                 stderr.write_all(e.to_string().as_bytes()).await.unwrap();
                 stderr.write_all("\n".as_bytes()).await.unwrap();
                 stderr.flush().await.unwrap();
-                std::process::exit(2);
+                std::process::exit(exit_code(&e));
             },
         }
     }
@@ -2,25 +2,79 @@ use std::str::FromStr;
 
 use measurements::Frequency;
 
-use crate::app::parser::number::Integer as _;
+use crate::app::parser::number::{scaled, split_unit};
 use crate::{Error, Result};
 
+// Parses a frequency in whole hertz, given as a decimal with an optional unit
+// of Hz, kHz, MHz or GHz. Values without a unit are in megahertz.
+fn hertz(s: &str) -> Result<u64> {
+    let (v, unit) = split_unit(s);
+    let exponent = match unit.to_ascii_lowercase().as_str() {
+        "hz" => 0,
+        "khz" => 3,
+        "" | "mhz" => 6,
+        "ghz" => 9,
+        _ => {
+            let e = format!("unknown frequency unit: {}", unit);
+            return Err(Error::parse_value(e));
+        },
+    };
+    let (hz, exact) = scaled(v, exponent)?;
+    if !exact {
+        let e = format!("frequency is not a whole number of hertz: {}", s);
+        return Err(Error::parse_value(e));
+    }
+    Ok(hz)
+}
+
+// Parses a nonzero frequency in whole multiples of `hz`, named `unit`.
+fn multiple(s: &str, hz: u64, unit: &str) -> Result<u64> {
+    let v = hertz(s)?;
+    if v < hz {
+        let e = format!("frequency is less than 1 {}: {}", unit, s);
+        return Err(Error::parse_value(e));
+    }
+    if 0 != v % hz {
+        let e = format!("frequency is not a whole number of {}: {}", unit, s);
+        return Err(Error::parse_value(e));
+    }
+    Ok(v / hz)
+}
+
+// A frequency in whole kilohertz, e.g. a cpufreq frequency.
+#[derive(Clone, Debug)]
+pub(super) struct Kilohertz(u64);
+
+impl FromStr for Kilohertz {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = Self(multiple(s, 1000, "kHz")?);
+        Ok(s)
+    }
+}
+
+impl From<Kilohertz> for Frequency {
+    fn from(v: Kilohertz) -> Self {
+        Frequency::from_kilohertz(v.0 as f64)
+    }
+}
+
+// A frequency in whole megahertz, e.g. an i915 or nvml gpu frequency.
 #[derive(Clone, Debug)]
-pub(super) struct Megahertz(Frequency);
+pub(super) struct Megahertz(u64);
 
 impl FromStr for Megahertz {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let i = u64::parse(s)?;
-        let f = Frequency::from_megahertz(i as f64);
-        let s = Self(f);
+        let s = Self(multiple(s, 1_000_000, "MHz")?);
         Ok(s)
     }
 }
 
 impl From<Megahertz> for Frequency {
     fn from(v: Megahertz) -> Self {
-        v.0
+        Frequency::from_megahertz(v.0 as f64)
     }
 }
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::str::FromStr;
+
+use measurements::{Frequency, Power};
+
+use crate::app::parser::number::Float;
+use crate::applet::{Knob, Value};
+use crate::{Error, Result};
+
+// Values which may be given relative to another value.
+pub(crate) trait Quantity: Copy {
+    // Converts a value read from a knob, in the unit of its attribute.
+    fn from_knob(knob: Knob, v: u64) -> Self;
+
+    fn scale(self, ratio: f64) -> Self;
+
+    // Adds or subtracts a value, or returns `None` if the result is negative.
+    fn offset(self, v: Self, negative: bool) -> Option<Self>;
+
+    // Checks a computed value, which must be at least 1 in the unit of the
+    // attribute of `knob` once rounded to it.
+    fn validate(self, knob: Knob) -> Result<Self>;
+}
+
+impl Quantity for Frequency {
+    fn from_knob(knob: Knob, v: u64) -> Self {
+        match knob {
+            Knob::CpuMinFreq(_) | Knob::CpuMaxFreq(_) => Frequency::from_kilohertz(v as f64),
+            _ => Frequency::from_megahertz(v as f64),
+        }
+    }
+
+    fn scale(self, ratio: f64) -> Self {
+        Frequency::from_hertz(self.as_hertz() * ratio)
+    }
+
+    fn offset(self, v: Self, negative: bool) -> Option<Self> {
+        let hz = if negative {
+            self.as_hertz() - v.as_hertz()
+        } else {
+            self.as_hertz() + v.as_hertz()
+        };
+        if hz < 0. { None } else { Some(Frequency::from_hertz(hz)) }
+    }
+
+    fn validate(self, knob: Knob) -> Result<Self> {
+        let (v, unit) = match knob {
+            Knob::CpuMinFreq(_) | Knob::CpuMaxFreq(_) => (self.as_kilohertz(), "kHz"),
+            _ => (self.as_megahertz(), "MHz"),
+        };
+        if v.round() < 1. {
+            let e = format!("frequency is less than 1 {}: {} Hz", unit, self.as_hertz());
+            return Err(Error::parse_value(e));
+        }
+        Ok(self)
+    }
+}
+
+impl Quantity for Power {
+    fn from_knob(knob: Knob, v: u64) -> Self {
+        match knob {
+            Knob::RaplLimit(..) => Power::from_microwatts(v as f64),
+            _ => Power::from_milliwatts(v as f64),
+        }
+    }
+
+    fn scale(self, ratio: f64) -> Self {
+        Power::from_watts(self.as_watts() * ratio)
+    }
+
+    fn offset(self, v: Self, negative: bool) -> Option<Self> {
+        let w = if negative {
+            self.as_watts() - v.as_watts()
+        } else {
+            self.as_watts() + v.as_watts()
+        };
+        if w < 0. { None } else { Some(Power::from_watts(w)) }
+    }
+
+    fn validate(self, knob: Knob) -> Result<Self> {
+        let (v, unit) = match knob {
+            Knob::RaplLimit(..) => (self.as_microwatts(), "µW"),
+            _ => (self.as_milliwatts(), "mW"),
+        };
+        if v.round() < 1. {
+            let e = format!("power is less than 1 {}: {} W", unit, self.as_watts());
+            return Err(Error::parse_value(e));
+        }
+        Ok(self)
+    }
+}
+
+// A value given absolutely, as a percentage of the maximum of each device, as
+// a named level of each device, e.g. `min`, `max` or `base`, or as an
+// adjustment of the current value of each device, e.g. `+5` or `-10%`.
+#[derive(Clone, Debug)]
+pub(crate) enum Level<T> {
+    Value(T),
+    Percent(f64),
+    Named(String),
+    // Negative when true.
+    Relative(T, bool),
+    // A signed percentage of the current value.
+    RelativePercent(f64),
+}
+
+impl<T> Level<T> {
+    pub(super) fn map<U>(self, f: impl FnOnce(T) -> U) -> Level<U> {
+        match self {
+            Self::Value(v) => Level::Value(f(v)),
+            Self::Percent(v) => Level::Percent(v),
+            Self::Named(v) => Level::Named(v),
+            Self::Relative(v, negative) => Level::Relative(f(v), negative),
+            Self::RelativePercent(v) => Level::RelativePercent(v),
+        }
+    }
+}
+
+fn percent(s: &str) -> Result<f64> {
+    let v = f64::parse(s.trim())?;
+    if !v.is_finite() || v <= 0. {
+        let e = format!("percentage must be greater than 0: {}", s);
+        return Err(Error::parse_value(e));
+    }
+    Ok(v)
+}
+
+impl<T> FromStr for Level<T>
+where
+    T: FromStr<Err = Error>,
+{
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let relative = match s.chars().next() {
+            Some('+') => Some(false),
+            Some('-') => Some(true),
+            _ => None,
+        };
+        if let Some(negative) = relative {
+            let v = &s[1..];
+            if let Some(v) = v.strip_suffix('%') {
+                let v = percent(v)?;
+                return Ok(Self::RelativePercent(if negative { -v } else { v }));
+            }
+            return T::from_str(v).map(|v| Self::Relative(v, negative));
+        }
+        if let Some(v) = s.strip_suffix('%') {
+            let v = percent(v)?;
+            if v > 100. {
+                let e = format!("percentage must be at most 100: {}", s);
+                return Err(Error::parse_value(e));
+            }
+            return Ok(Self::Percent(v));
+        }
+        if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Ok(Self::Named(s.to_ascii_lowercase()));
+        }
+        T::from_str(s).map(Self::Value)
+    }
+}
+
+// Reads the current value of a knob, for adjustments.
+async fn current<T: Quantity>(knob: Knob) -> Result<T> {
+    match knob.read().await {
+        Some(Value::Int(v)) => Ok(T::from_knob(knob, v)),
+        _ => Err(Error::parse_value(format!(
+            "{} {}: could not read current value",
+            knob.device(),
+            knob.attribute()
+        ))),
+    }
+}
+
+impl<T: Quantity> Level<T> {
+    // Resolves the level of a device. `named` returns a named level of the
+    // device, or `None` if the device has no level of that name. Percentages
+    // are of the `max` level. Adjustments are of the current value of `knob`,
+    // read when arguments are parsed, before any argument group is run.
+    async fn resolve<N, NF>(&self, named: N, knob: Knob) -> Result<T>
+    where
+        N: FnOnce(String) -> NF,
+        NF: Future<Output = Result<Option<T>>>,
+    {
+        let (name, ratio) = match self {
+            Self::Value(v) => return Ok(*v),
+            Self::Percent(v) => ("max", v / 100.),
+            Self::Named(v) => (v.as_str(), 1.),
+            Self::Relative(v, negative) => {
+                let r = current::<T>(knob).await?.offset(*v, *negative).ok_or_else(|| {
+                    Error::parse_value("adjustment would make the value negative")
+                })?;
+                return r.validate(knob);
+            },
+            Self::RelativePercent(v) => {
+                if *v <= -100. {
+                    let e = "adjustment would make the value zero or negative";
+                    return Err(Error::parse_value(e));
+                }
+                return current::<T>(knob).await?.scale(1. + v / 100.).validate(knob);
+            },
+        };
+        let v = named(name.to_string())
+            .await?
+            .ok_or_else(|| Error::parse_value(format!("unknown level: {}", name)))?;
+        v.scale(ratio).validate(knob)
+    }
+}
+
+// Resolves a level for each device id. `knob` returns the knob of an id
+// which is set to the level.
+pub(crate) async fn resolve<T, N, NF, K>(
+    level: Option<Level<T>>,
+    ids: Option<&[u64]>,
+    named: N,
+    knob: K,
+) -> Result<Option<HashMap<u64, T>>>
+where
+    T: Quantity,
+    N: Fn(u64, String) -> NF,
+    NF: Future<Output = Result<Option<T>>>,
+    K: Fn(u64) -> Knob,
+{
+    let (level, ids) = match (level, ids) {
+        (Some(level), Some(ids)) => (level, ids),
+        _ => return Ok(None),
+    };
+    let mut r = HashMap::new();
+    for &id in ids {
+        let v = level.resolve(|name| named(id, name), knob(id)).await?;
+        r.insert(id, v);
+    }
+    Ok(Some(r))
+}
@@ -2,6 +2,7 @@ mod bool;
 mod cpu;
 mod drm;
 mod frequency;
+mod level;
 mod number;
 mod power;
 mod rapl;
@@ -13,8 +14,10 @@ use std::time::Duration;
 use measurements::{Frequency, Power};
 
 pub(crate) use crate::app::parser::drm::{DrmDriver, I915Driver, NvmlDriver};
+pub(crate) use crate::app::parser::level::{resolve as resolve_level, Level};
 use crate::app::parser::number::Integer;
 use crate::applet::RaplConstraintIds;
+use crate::util::format::Format;
 use crate::{Error, Result};
 
 #[derive(Debug)]
@@ -64,8 +67,19 @@ impl<'a> Parser<'a> {
         if self.0.is_present(name) { Some(()) } else { None }
     }
 
-    pub(crate) fn int<I: Integer>(&self, name: &str) -> Result<Option<I>> {
-        self.str(name).map(I::parse).transpose().map_err(|e| Error::parse_flag(e, name))
+    pub(crate) fn format(&self, name: &str) -> Result<Option<Format>> {
+        self.str(name)
+            .map(Format::from_str)
+            .transpose()
+            .map_err(|e| Error::parse_flag(e, name))
+    }
+
+    pub(crate) fn kilohertz_level(&self, name: &str) -> Result<Option<Level<Frequency>>> {
+        self.str(name)
+            .map(level::Level::<frequency::Kilohertz>::from_str)
+            .transpose()
+            .map(|v| v.map(|v| v.map(Into::into)))
+            .map_err(|e| Error::parse_flag(e, name))
     }
 
     pub(crate) fn megahertz(&self, name: &str) -> Result<Option<Frequency>> {
@@ -76,6 +90,28 @@ impl<'a> Parser<'a> {
             .map_err(|e| Error::parse_flag(e, name))
     }
 
+    pub(crate) fn megahertz_level(&self, name: &str) -> Result<Option<Level<Frequency>>> {
+        self.str(name)
+            .map(level::Level::<frequency::Megahertz>::from_str)
+            .transpose()
+            .map(|v| v.map(|v| v.map(Into::into)))
+            .map_err(|e| Error::parse_flag(e, name))
+    }
+
+    pub(crate) fn int<I: Integer>(&self, name: &str) -> Result<Option<I>> {
+        self.str(name).map(I::parse).transpose().map_err(|e| Error::parse_flag(e, name))
+    }
+
+    pub(crate) fn list(&self, name: &str) -> Option<Vec<String>> {
+        self.str(name).map(|v| {
+            v.split(',')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(String::from)
+                .collect()
+        })
+    }
+
     pub(crate) fn microseconds(&self, name: &str) -> Result<Option<Duration>> {
         self.str(name)
             .map(time::Microseconds::from_str)
@@ -107,6 +143,14 @@ impl<'a> Parser<'a> {
         Ok(None)
     }
 
+    pub(crate) fn seconds(&self, name: &str) -> Result<Option<Duration>> {
+        self.str(name)
+            .map(time::Seconds::from_str)
+            .transpose()
+            .map(|v| v.map(Into::into))
+            .map_err(|e| Error::parse_flag(e, name))
+    }
+
     pub(crate) fn str(&self, name: &str) -> Option<&str> {
         self.0.value_of(name)
     }
@@ -115,11 +159,23 @@ impl<'a> Parser<'a> {
         self.str(name).map(String::from)
     }
 
-    pub(crate) fn watts(&self, name: &str) -> Result<Option<Power>> {
+    pub(crate) fn strings(&self, name: &str) -> Option<Vec<String>> {
+        self.0.values_of(name).map(|v| v.map(String::from).collect())
+    }
+
+    pub(crate) fn microwatts_level(&self, name: &str) -> Result<Option<Level<Power>>> {
         self.str(name)
-            .map(power::Watts::from_str)
+            .map(level::Level::<power::Microwatts>::from_str)
             .transpose()
-            .map(|v| v.map(Into::into))
+            .map(|v| v.map(|v| v.map(Into::into)))
+            .map_err(|e| Error::parse_flag(e, name))
+    }
+
+    pub(crate) fn milliwatts_level(&self, name: &str) -> Result<Option<Level<Power>>> {
+        self.str(name)
+            .map(level::Level::<power::Milliwatts>::from_str)
+            .transpose()
+            .map(|v| v.map(|v| v.map(Into::into)))
             .map_err(|e| Error::parse_flag(e, name))
     }
 }
@@ -21,3 +21,32 @@ pub(crate) trait Float: PartialEq<Self> + PartialOrd<Self> + Copy + FromStr {
 }
 
 impl Float for f64 {}
+
+// Splits a value into its number and unit suffix, e.g. `2.4GHz` into `2.4`
+// and `GHz`.
+pub(crate) fn split_unit(s: &str) -> (&str, &str) {
+    let s = s.trim();
+    let i = s.find(|c: char| !c.is_ascii_digit() && '.' != c).unwrap_or(s.len());
+    (s[..i].trim(), s[i..].trim())
+}
+
+// Parses a non-negative decimal as an integer multiple of 10^-exponent, e.g.
+// `2.4` with exponent 6 as 2_400_000. Digits past the exponent are truncated,
+// and the returned bool is false if any was not zero.
+pub(crate) fn scaled(s: &str, exponent: usize) -> Result<(u64, bool)> {
+    let err = || Error::parse_value(format!("could not parse as decimal: {}", s));
+    let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+    let digits = || int.chars().chain(frac.chars());
+    if int.is_empty() && frac.is_empty() || !digits().all(|c| c.is_ascii_digit()) {
+        return Err(err());
+    }
+    let (kept, dropped) = frac.split_at(frac.len().min(exponent));
+    let mut v: u64 = 0;
+    for c in int.chars().chain(kept.chars()) {
+        let d = c.to_digit(10).expect("decimal digit") as u64;
+        v = v.checked_mul(10).and_then(|v| v.checked_add(d)).ok_or_else(err)?;
+    }
+    let scale = 10u64.checked_pow((exponent - kept.len()) as u32);
+    let v = scale.and_then(|scale| v.checked_mul(scale)).ok_or_else(err)?;
+    Ok((v, dropped.chars().all(|c| '0' == c)))
+}
@@ -2,25 +2,70 @@ use std::str::FromStr;
 
 use measurements::Power;
 
-use crate::app::parser::number::Float;
+use crate::app::parser::number::{scaled, split_unit};
 use crate::{Error, Result};
 
+// Parses a power in whole microwatts, given as a decimal with an optional unit
+// of uW, mW, W or kW. Values without a unit are in watts.
+fn microwatts(s: &str) -> Result<u64> {
+    let (v, unit) = split_unit(s);
+    let exponent = match unit.to_ascii_lowercase().as_str() {
+        "uw" | "μw" | "µw" => 0,
+        "mw" => 3,
+        "" | "w" => 6,
+        "kw" => 9,
+        _ => {
+            let e = format!("unknown power unit: {}", unit);
+            return Err(Error::parse_value(e));
+        },
+    };
+    let (uw, exact) = scaled(v, exponent)?;
+    if !exact {
+        let e = format!("power is not a whole number of microwatts: {}", s);
+        return Err(Error::parse_value(e));
+    }
+    Ok(uw)
+}
+
+// A power in whole microwatts, e.g. a rapl power limit.
+#[derive(Clone, Debug)]
+pub(super) struct Microwatts(u64);
+
+impl FromStr for Microwatts {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = Self(microwatts(s)?);
+        Ok(s)
+    }
+}
+
+impl From<Microwatts> for Power {
+    fn from(v: Microwatts) -> Self {
+        Power::from_microwatts(v.0 as f64)
+    }
+}
+
+// A power in whole milliwatts, e.g. an nvml power limit.
 #[derive(Clone, Debug)]
-pub(super) struct Watts(Power);
+pub(super) struct Milliwatts(u64);
 
-impl FromStr for Watts {
+impl FromStr for Milliwatts {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let v = f64::parse(s)?;
-        let v = Power::from_watts(v);
-        let s = Self(v);
+        let uw = microwatts(s)?;
+        if 0 != uw % 1000 {
+            let e = format!("power is not a whole number of milliwatts: {}", s);
+            return Err(Error::parse_value(e));
+        }
+        let s = Self(uw / 1000);
         Ok(s)
     }
 }
 
-impl From<Watts> for Power {
-    fn from(v: Watts) -> Self {
-        v.0
+impl From<Milliwatts> for Power {
+    fn from(v: Milliwatts) -> Self {
+        Power::from_milliwatts(v.0 as f64)
     }
 }
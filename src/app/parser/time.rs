@@ -1,9 +1,11 @@
 use std::str::FromStr;
 use std::time::Duration;
 
-use crate::app::parser::number::Integer;
+use crate::app::parser::number::{scaled, split_unit, Float};
 use crate::{Error, Result};
 
+// A duration in whole microseconds, given as a decimal with an optional unit
+// of us, ms or s. Values without a unit are in microseconds.
 #[derive(Clone, Debug)]
 pub(super) struct Microseconds(Duration);
 
@@ -11,7 +13,21 @@ impl FromStr for Microseconds {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let v = u64::parse(s)?;
+        let (v, unit) = split_unit(s);
+        let exponent = match unit.to_ascii_lowercase().as_str() {
+            "" | "us" | "μs" | "µs" => 0,
+            "ms" => 3,
+            "s" => 6,
+            _ => {
+                let e = format!("unknown time unit: {}", unit);
+                return Err(Error::parse_value(e));
+            },
+        };
+        let (v, exact) = scaled(v, exponent)?;
+        if !exact {
+            let e = format!("time is not a whole number of microseconds: {}", s);
+            return Err(Error::parse_value(e));
+        }
         let v = Duration::from_micros(v);
         let s = Self(v);
         Ok(s)
@@ -23,3 +39,38 @@ impl From<Microseconds> for Duration {
         v.0
     }
 }
+
+// Longer intervals overflow durations and deadlines.
+const SECONDS_MAX: f64 = 365. * 24. * 60. * 60.;
+
+#[derive(Clone, Debug)]
+pub(super) struct Seconds(Duration);
+
+impl FromStr for Seconds {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let v = f64::parse(s)?;
+        if !v.is_finite() || v <= 0. {
+            let e = format!("could not parse as positive seconds: {}", s);
+            return Err(Error::parse_value(e));
+        }
+        if v > SECONDS_MAX {
+            let e = format!("seconds must be at most {}: {}", SECONDS_MAX, s);
+            return Err(Error::parse_value(e));
+        }
+        let v = Duration::from_secs_f64(v);
+        if v.is_zero() {
+            let e = format!("could not parse as positive seconds: {}", s);
+            return Err(Error::parse_value(e));
+        }
+        let s = Self(v);
+        Ok(s)
+    }
+}
+
+impl From<Seconds> for Duration {
+    fn from(v: Seconds) -> Self {
+        v.0
+    }
+}
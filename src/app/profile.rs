@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use tokio::fs::{read_dir, read_to_string};
+use toml::value::{Table as TomlTable, Value};
+
+use crate::app::{Arg, Parser, NAME};
+use crate::util::format::{Column, Section, Table, Unit};
+use crate::{Error, Result};
+
+pub(super) const APPLY: &str = "apply";
+pub(super) const PROFILE: &str = "profile";
+
+const FILE: &str = "file";
+const COMMAND: &str = "command";
+const PROFILE_NAME: &str = "name";
+
+const APPLY_ABOUT: &str = "Apply a profile file";
+const PROFILE_ABOUT: &str = "List, show or apply named profiles";
+
+const FILE_HELP: &str = "Profile file path";
+const COMMAND_HELP: &str = "One of list, show, apply";
+const PROFILE_NAME_HELP: &str = "Profile name";
+
+const SYSTEM_DIR: &str = "/etc/knobs/profiles";
+
+// The subcommands which may appear as profile tables.
+const TABLES: &[&str] = &["cpu", "rapl", "i915", "nvml"];
+
+const LIST: &[Column] = &[
+    Column::new("Name", "name", Unit::None),
+    Column::new("Path", "path", Unit::None),
+];
+
+const SHOW: &[Column] = &[
+    Column::new("Group", "group", Unit::None),
+    Column::new("Arguments", "arguments", Unit::None),
+];
+
+#[rustfmt::skip]
+fn file_help_long() -> String {
+"Profile file path. Each table of the profile is an
+argument group for the subcommand of the same name,
+with one key per flag. Tables are applied in order.
+Example: [cpu] gov = \"performance\"".to_string()
+}
+
+#[rustfmt::skip]
+fn command_help_long() -> String {
+"list: print the profiles found in the search path
+show NAME: print the argument groups of a profile
+apply NAME: apply a profile
+Search path: $XDG_CONFIG_HOME/knobs/profiles
+             /etc/knobs/profiles".to_string()
+}
+
+// Returns the profile subcommands, as names, abouts and arguments.
+pub(super) fn subcommands() -> Vec<(&'static str, &'static str, Vec<Arg>)> {
+    let apply_args = vec![Arg {
+        name: FILE.into(),
+        value_name: "FILE".into(),
+        help: FILE_HELP.into(),
+        help_long: file_help_long().into(),
+        required: true.into(),
+        ..Default::default()
+    }];
+    let profile_args = vec![
+        Arg {
+            name: COMMAND.into(),
+            value_name: "CMD".into(),
+            help: COMMAND_HELP.into(),
+            help_long: command_help_long().into(),
+            required: true.into(),
+            ..Default::default()
+        },
+        Arg {
+            name: PROFILE_NAME.into(),
+            value_name: "NAME".into(),
+            help: PROFILE_NAME_HELP.into(),
+            ..Default::default()
+        },
+    ];
+    vec![
+        (APPLY, APPLY_ABOUT, apply_args),
+        (PROFILE, PROFILE_ABOUT, profile_args),
+    ]
+}
+
+// Returns the profile directories, in order of precedence.
+fn dirs() -> Vec<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|v| PathBuf::from(v).join(".config")));
+    config
+        .map(|v| v.join(NAME).join("profiles"))
+        .into_iter()
+        .chain(Some(PathBuf::from(SYSTEM_DIR)))
+        .collect()
+}
+
+// Returns the path of each profile by name. Profiles found earlier in the
+// search path hide those of the same name found later.
+async fn list() -> BTreeMap<String, PathBuf> {
+    let mut r = BTreeMap::new();
+    for dir in dirs() {
+        let mut entries = match read_dir(&dir).await {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().map(|v| v == "toml").unwrap_or(false) {
+                if let Some(name) = path.file_stem().and_then(|v| v.to_str()) {
+                    r.entry(name.to_string()).or_insert(path);
+                }
+            }
+        }
+    }
+    r
+}
+
+pub(super) async fn resolve(name: &str) -> Result<String> {
+    list()
+        .await
+        .remove(name)
+        .map(|v| v.display().to_string())
+        .ok_or_else(|| Error::parse_value(format!("profile not found: {}", name)))
+}
+
+// An argument group read from a profile, named after its table for errors.
+#[derive(Debug)]
+pub(super) struct Group {
+    pub(super) name: String,
+    pub(super) argv: Vec<String>,
+}
+
+fn value(v: &Value) -> Option<String> {
+    match v {
+        Value::String(v) => Some(v.clone()),
+        Value::Integer(v) => Some(v.to_string()),
+        Value::Float(v) => Some(v.to_string()),
+        Value::Boolean(v) => Some(v.to_string()),
+        Value::Array(v) => v.iter().map(value).collect::<Option<Vec<_>>>().map(|v| v.join(",")),
+        _ => None,
+    }
+}
+
+// Subcommand names, abouts and arguments, as given to clap.
+pub(super) type Subcommands = [(&'static str, &'static str, Vec<Arg>)];
+
+// Returns true if the subcommand's flag takes no value.
+fn is_flag(subcmds: &Subcommands, subcmd: &str, flag: &str) -> bool {
+    subcmds
+        .iter()
+        .filter(|(n, _, _)| subcmd == *n)
+        .any(|(_, _, args)| args.iter().any(|a| Some(flag) == a.long && a.value_name.is_none()))
+}
+
+// Returns true if the subcommand's flag takes a value which may be adjusted,
+// e.g. `+200` or `-10%`.
+fn is_adjustable(subcmds: &Subcommands, subcmd: &str, flag: &str) -> bool {
+    subcmds.iter().filter(|(n, _, _)| subcmd == *n).any(|(_, _, args)| {
+        args.iter().any(|a| Some(flag) == a.long && Some(true) == a.allow_hyphen_values)
+    })
+}
+
+// Flags which take no value are given as `true` or `false`. Adjustments are
+// rejected, as they would shift values again each time a profile is applied.
+fn group(subcmds: &Subcommands, subcmd: &str, name: String, table: &TomlTable) -> Result<Group> {
+    let mut argv = vec![subcmd.to_string()];
+    for (k, v) in table {
+        match v {
+            Value::Boolean(true) if is_flag(subcmds, subcmd, k) => argv.push(format!("--{}", k)),
+            Value::Boolean(false) if is_flag(subcmds, subcmd, k) => {},
+            v => {
+                let v = value(v).ok_or_else(|| {
+                    Error::parse_value(format!(
+                        "{}: {}: expected a string, number, bool or array",
+                        name, k
+                    ))
+                })?;
+                if is_adjustable(subcmds, subcmd, k) && v.trim_start().starts_with(['+', '-']) {
+                    return Err(Error::parse_value(format!(
+                        "{}: {}: adjustments cannot be applied from profiles: {}",
+                        name, k, v
+                    )));
+                }
+                argv.push(format!("--{}={}", k, v));
+            },
+        }
+    }
+    Ok(Group { name, argv })
+}
+
+// Converts each table to an argument group. An array of tables yields one
+// argument group per table.
+fn parse(subcmds: &Subcommands, s: &str) -> Result<Vec<Group>> {
+    let tables: TomlTable = toml::from_str(s).map_err(Error::parse_value)?;
+    let mut r = vec![];
+    for (subcmd, v) in &tables {
+        if !TABLES.contains(&subcmd.as_str()) {
+            let e = format!("{}: expected one of {}", subcmd, TABLES.join(", "));
+            return Err(Error::parse_value(e));
+        }
+        match v {
+            Value::Table(v) => r.push(group(subcmds, subcmd, subcmd.clone(), v)?),
+            Value::Array(v) if v.iter().all(Value::is_table) => {
+                for (i, v) in v.iter().enumerate() {
+                    let name = format!("{} {}", subcmd, i + 1);
+                    let v = v.as_table().expect("profile table");
+                    r.push(group(subcmds, subcmd, name, v)?);
+                }
+            },
+            _ => return Err(Error::parse_value(format!("{}: expected a table", subcmd))),
+        }
+    }
+    Ok(r)
+}
+
+pub(super) async fn read(path: &str, subcmds: &Subcommands) -> Result<Vec<Group>> {
+    log::trace!("profile read start");
+    let s = read_to_string(path).await.map_err(|e| Error::io(e, path))?;
+    let r = parse(subcmds, &s).map_err(|e| Error::profile(e, path))?;
+    log::trace!("profile read done");
+    Ok(r)
+}
+
+async fn show(name: &str, subcmds: &Subcommands) -> Result<Vec<Section>> {
+    let path = resolve(name).await?;
+    let mut tab = Table::new("profile", SHOW);
+    for group in read(&path, subcmds).await? {
+        tab.row([group.name.into(), group.argv.join(" ").into()]);
+    }
+    Ok(vec![Section::Status("path", path), tab.into()])
+}
+
+#[derive(Debug)]
+pub(super) enum Command {
+    // Apply the profile at the given path.
+    Apply(String),
+    // Print the reports.
+    Report(Vec<Section>),
+}
+
+// Parses the arguments of `apply` or `profile`.
+pub(super) async fn command(subcmd: &str, p: Parser<'_>, subcmds: &Subcommands) -> Result<Command> {
+    log::trace!("profile command start");
+    // Arguments are only looked up for the subcommand which defines them.
+    if APPLY == subcmd {
+        log::trace!("profile command done");
+        return Ok(Command::Apply(p.string(FILE).expect("profile file")));
+    }
+    let name = p.string(PROFILE_NAME);
+    let name = || name.as_deref().ok_or_else(|| Error::parse_value("missing profile name"));
+    let r = match p.str(COMMAND) {
+        Some("list") => {
+            let mut tab = Table::new("profiles", LIST);
+            for (name, path) in list().await {
+                tab.row([name.into(), path.display().to_string().into()]);
+            }
+            Command::Report(vec![tab.into()])
+        },
+        Some("show") => Command::Report(show(name()?, subcmds).await?),
+        Some("apply") => Command::Apply(resolve(name()?).await?),
+        v => {
+            let v = v.unwrap_or_default();
+            return Err(Error::parse_value(format!(
+                "unknown profile command: {}",
+                v
+            )));
+        },
+    };
+    log::trace!("profile command done");
+    Ok(r)
+}
@@ -1,5 +1,9 @@
-use crate::app::{Arg, Parser};
-use crate::Result;
+use futures::stream::TryStreamExt as _;
+use measurements::Frequency;
+
+use crate::app::{resolve_level, Arg, Level, Parser};
+use crate::applet::Knob;
+use crate::{Error, Result};
 
 const CPU: &str = "cpu";
 const ON: &str = "on";
@@ -20,8 +24,8 @@ const EPP_SHORT: char = 'p';
 const CPU_HELP: &str = "Target cpu ids";
 const ON_HELP: &str = "Set cpu online or offline";
 const GOV_HELP: &str = "Set cpu governor";
-const MIN_HELP: &str = "Set cpu min freq in megahertz";
-const MAX_HELP: &str = "Set cpu max freq in megahertz";
+const MIN_HELP: &str = "Set cpu min freq, in megahertz by default";
+const MAX_HELP: &str = "Set cpu max freq, in megahertz by default";
 const EPB_HELP: &str = "Set cpu epb";
 const EPP_HELP: &str = "Set cpu epp";
 
@@ -44,12 +48,26 @@ fn gov_help_long() -> String {
     format!("Set cpu governor per -{}/--{}", CPU_SHORT, CPU)
 }
 
+#[rustfmt::skip]
 fn min_help_long() -> String {
-    format!("Set cpu min freq in megahertz per -{}/--{}", CPU_SHORT, CPU)
+    format!(
+"Set cpu min freq per -{}/--{}, in megahertz
+unless a unit of Hz, kHz, MHz or GHz is given
+Frequency syntax: 800 1.5GHz 800MHz 1200000kHz
+Level syntax: min max base 50%
+Adjustment syntax: +200 -200 -10%",
+CPU_SHORT, CPU)
 }
 
+#[rustfmt::skip]
 fn max_help_long() -> String {
-    format!("Set cpu max freq in megahertz per -{}/--{}", CPU_SHORT, CPU)
+    format!(
+"Set cpu max freq per -{}/--{}, in megahertz
+unless a unit of Hz, kHz, MHz or GHz is given
+Frequency syntax: 3000 2.4GHz 2400MHz
+Level syntax: min max base 80%
+Adjustment syntax: +200 -200 -10%",
+CPU_SHORT, CPU)
 }
 
 fn epb_help_long() -> String {
@@ -101,20 +119,22 @@ pub(super) fn args() -> Vec<Arg> {
             name: MIN.into(),
             long: MIN.into(),
             short: MIN_SHORT.into(),
-            value_name: "INT".into(),
+            value_name: "FREQ".into(),
             help: MIN_HELP.into(),
             help_long: min_help_long().into(),
             requires: vec![CPU].into(),
+            allow_hyphen_values: true.into(),
             ..Default::default()
         },
         Arg {
             name: MAX.into(),
             long: MAX.into(),
             short: MAX_SHORT.into(),
-            value_name: "INT".into(),
+            value_name: "FREQ".into(),
             help: MAX_HELP.into(),
             help_long: max_help_long().into(),
             requires: vec![CPU].into(),
+            allow_hyphen_values: true.into(),
             ..Default::default()
         },
         Arg {
@@ -140,15 +160,60 @@ pub(super) fn args() -> Vec<Arg> {
     ]
 }
 
+async fn level(id: u64, name: String) -> Result<Option<Frequency>> {
+    let khz = match name.as_str() {
+        "min" => syx::cpufreq::Values::new(id).cpuinfo_min_freq().await,
+        "max" => syx::cpufreq::Values::new(id).cpuinfo_max_freq().await,
+        "base" => syx::intel_pstate::policy::Values::new(id).base_frequency().await,
+        _ => return Ok(None),
+    };
+    let khz = khz.map_err(|e| Error::parse_value(format!("cpu {} {}: {}", id, name, e)))?;
+    Ok(Some(Frequency::from_kilohertz(khz as f64)))
+}
+
+// Levels other than plain frequencies are read from each cpu, so cpus which
+// are offline, and cannot be read, are refused.
+async fn check_online(level: &Option<Level<Frequency>>, ids: Option<&[u64]>) -> Result<()> {
+    if matches!(level, None | Some(Level::Value(_))) {
+        return Ok(());
+    }
+    let offline: Vec<_> = syx::cpu::offline_ids().try_collect().await?;
+    let ids: Vec<_> = ids.unwrap_or_default().iter().filter(|v| offline.contains(v)).collect();
+    if !ids.is_empty() {
+        let ids: Vec<_> = ids.iter().map(ToString::to_string).collect();
+        let e = format!(
+            "levels and adjustments require online cpus, offline: {}",
+            ids.join(",")
+        );
+        return Err(Error::parse_value(e));
+    }
+    Ok(())
+}
+
 impl super::Values {
     pub(super) async fn from_parser(p: Parser<'_>) -> Result<Self> {
         log::trace!("cpu parse start");
+        let ids = p.cpu_ids(CPU).await?;
+        let min = p.kilohertz_level(MIN)?;
+        check_online(&min, ids.as_deref())
+            .await
+            .map_err(|e| Error::parse_flag(e, MIN))?;
+        let min = resolve_level(min, ids.as_deref(), level, Knob::CpuMinFreq)
+            .await
+            .map_err(|e| Error::parse_flag(e, MIN))?;
+        let max = p.kilohertz_level(MAX)?;
+        check_online(&max, ids.as_deref())
+            .await
+            .map_err(|e| Error::parse_flag(e, MAX))?;
+        let max = resolve_level(max, ids.as_deref(), level, Knob::CpuMaxFreq)
+            .await
+            .map_err(|e| Error::parse_flag(e, MAX))?;
         let r = Self {
-            ids: p.cpu_ids(CPU).await?,
+            ids,
             on: p.bool(ON)?,
             gov: p.string(GOV),
-            min: p.megahertz(MIN)?,
-            max: p.megahertz(MAX)?,
+            min,
+            max,
             epb: p.int(EPB)?,
             epp: p.string(EPP),
         };
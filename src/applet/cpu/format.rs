@@ -1,19 +1,41 @@
 use futures::future::{join_all, FutureExt as _};
-use measurements::Frequency;
 use syx::cpu::Values as Cpu;
 use syx::cpufreq::Values as Cpufreq;
 use syx::intel_pstate::policy::Values as PstatePolicy;
 use syx::intel_pstate::system::Cache as PstateSystem;
 
-use crate::applet::Formatter;
-use crate::util::format::{dot, frequency, Table, DOT};
+use crate::applet::{Formatter, Id};
+use crate::util::format::{Cell, Column, Section, Table, Unit};
 use crate::util::once;
 
-fn khz(v: u64) -> String {
-    frequency(Frequency::from_kilohertz(v as f64))
-}
+const CPU_CPUFREQ: &[Column] = &[
+    Column::new("CPU", "cpu", Unit::None),
+    Column::new("Online", "online", Unit::None),
+    Column::new("Governor", "governor", Unit::None),
+    Column::new("Cur", "cur", Unit::Khz),
+    Column::new("Min", "min", Unit::Khz),
+    Column::new("Max", "max", Unit::Khz),
+    Column::new("Min lim", "min_lim", Unit::Khz),
+    Column::new("Max lim", "max_lim", Unit::Khz),
+];
+
+const GOVERNORS: &[Column] = &[
+    Column::new("CPU", "cpu", Unit::None),
+    Column::new("Available governors", "governors", Unit::None),
+];
+
+const EPB_EPP: &[Column] = &[
+    Column::new("CPU", "cpu", Unit::None),
+    Column::new("EP bias", "epb", Unit::None),
+    Column::new("EP preference", "epp", Unit::None),
+];
 
-async fn cpu_cpufreq(cpus: Vec<Cpu>, mut cpufreqs: Vec<Cpufreq>) -> Option<String> {
+const EPPS: &[Column] = &[
+    Column::new("CPU", "cpu", Unit::None),
+    Column::new("Available EP preferences", "epps", Unit::None),
+];
+
+async fn cpu_cpufreq(cpus: Vec<Cpu>, mut cpufreqs: Vec<Cpufreq>) -> Option<Section> {
     log::trace!("cpu format cpu_cpufreq start");
     if cpus.is_empty() {
         log::trace!("cpu format cpu_cpufreq none");
@@ -25,67 +47,62 @@ async fn cpu_cpufreq(cpus: Vec<Cpu>, mut cpufreqs: Vec<Cpufreq>) -> Option<Strin
                 .position(|cpufreq| cpufreq.id() == cpu.id())
                 .map(|i| cpufreqs.swap_remove(i));
             async move {
-                let mut row = vec![
-                    cpu.id().to_string(),
-                    cpu.online().await.ok().map(|v| v.to_string()).unwrap_or_else(dot),
-                ];
+                let mut row = vec![cpu.id().into(), cpu.online().await.ok().into()];
                 if let Some(cpufreq) = cpufreq {
                     row.extend([
-                        cpufreq.scaling_governor().await.ok().unwrap_or_else(dot),
-                        cpufreq.scaling_cur_freq().await.ok().map(khz).unwrap_or_else(dot),
-                        cpufreq.scaling_min_freq().await.ok().map(khz).unwrap_or_else(dot),
-                        cpufreq.scaling_max_freq().await.ok().map(khz).unwrap_or_else(dot),
-                        cpufreq.cpuinfo_min_freq().await.ok().map(khz).unwrap_or_else(dot),
-                        cpufreq.cpuinfo_max_freq().await.ok().map(khz).unwrap_or_else(dot),
+                        cpufreq.scaling_governor().await.ok().into(),
+                        cpufreq.scaling_cur_freq().await.ok().into(),
+                        cpufreq.scaling_min_freq().await.ok().into(),
+                        cpufreq.scaling_max_freq().await.ok().into(),
+                        cpufreq.cpuinfo_min_freq().await.ok().into(),
+                        cpufreq.cpuinfo_max_freq().await.ok().into(),
                     ]);
                 } else {
-                    row.extend([dot(), dot(), dot(), dot(), dot(), dot()]);
+                    row.extend([
+                        Cell::Null,
+                        Cell::Null,
+                        Cell::Null,
+                        Cell::Null,
+                        Cell::Null,
+                        Cell::Null,
+                    ]);
                 }
                 row
             }
         }))
         .await;
         drop(cpufreqs);
-        let mut tab = Table::new(&[
-            "CPU", "Online", "Governor", "Cur", "Min", "Max", "Min lim", "Max lim",
-        ]);
+        let mut tab = Table::new("cpu", CPU_CPUFREQ);
         tab.rows(rows);
+        tab.collapse(&["cur"]);
         let r = Some(tab.into());
         log::trace!("cpu format cpu_cpufreq done");
         r
     }
 }
 
-async fn governors(cpufreqs: Vec<Cpufreq>) -> Option<String> {
+async fn governors(cpufreqs: Vec<Cpufreq>) -> Option<Section> {
     log::trace!("cpu format governors start");
     if cpufreqs.is_empty() {
         log::trace!("cpu format governors none");
         None
     } else {
         let values = join_all(cpufreqs.into_iter().map(|v| async move {
-            let id = v.id().to_string();
-            let govs = v
-                .scaling_available_governors()
-                .await
-                .ok()
-                .map(|g| g.join(" "))
-                .unwrap_or_else(dot);
+            let id = Cell::from(v.id());
+            let govs = v.scaling_available_governors().await.ok().into();
             [id, govs]
         }))
         .await;
-        let mut govs: Vec<_> = values.iter().map(|[_, g]| g.as_str()).collect();
+        let mut govs: Vec<_> = values.iter().map(|[_, g]| g).collect();
         govs.sort_unstable();
         govs.dedup();
-        if govs.is_empty() || (govs.len() == 1 && govs[0] == DOT) {
+        if govs.is_empty() || (govs.len() == 1 && govs[0] == &Cell::Null) {
             log::trace!("cpu governors none 2");
             None
         } else {
-            let mut tab = Table::new(&["CPU", "Available governors"]);
-            if govs.len() == 1 {
-                tab.row(["all".to_string(), govs[0].to_string()]);
-            } else {
-                tab.rows(values);
-            }
+            let mut tab = Table::new("cpu_governors", GOVERNORS);
+            tab.rows(values);
+            tab.collapse(&[]);
             let r = Some(tab.into());
             log::trace!("cpu format governors done");
             r
@@ -93,7 +110,7 @@ async fn governors(cpufreqs: Vec<Cpufreq>) -> Option<String> {
     }
 }
 
-async fn pstate_status(system: PstateSystem) -> Option<String> {
+async fn pstate_status(system: PstateSystem) -> Option<Section> {
     log::trace!("cpu format pstate_status start");
     if system.is_active().await.unwrap_or(false) {
         log::trace!("cpu format pstate_status none");
@@ -101,46 +118,35 @@ async fn pstate_status(system: PstateSystem) -> Option<String> {
     } else {
         // Print the status when not active so that the user
         // knows why they're not seeing the epb/epp tables.
-        let r = system.status().await.ok().map(|v| format!(" intel_pstate: {}\n", v));
+        let r = system.status().await.ok().map(|v| Section::Status("intel_pstate", v));
         log::trace!("cpu format pstate_status done");
         r
     }
 }
 
-async fn epb_epp(system: PstateSystem, pstates: Vec<PstatePolicy>) -> Option<String> {
+async fn epb_epp(system: PstateSystem, pstates: Vec<PstatePolicy>) -> Option<Section> {
     log::trace!("cpu format epb_epp start");
     if pstates.is_empty() || !system.is_active().await.ok().unwrap_or(false) {
         log::trace!("cpu format epb_epp none");
         None
     } else {
         let values = join_all(pstates.into_iter().map(|v| async move {
-            let id = v.id().to_string();
-            let epb = v
-                .energy_perf_bias()
-                .await
-                .ok()
-                .as_ref()
-                .map(ToString::to_string)
-                .unwrap_or_else(dot);
-            let epp = v.energy_performance_preference().await.ok().unwrap_or_else(dot);
+            let id = Cell::from(v.id());
+            let epb = v.energy_perf_bias().await.ok().into();
+            let epp = v.energy_performance_preference().await.ok().into();
             [id, epb, epp]
         }))
         .await;
-        let mut epb_epp: Vec<_> =
-            values.iter().map(|[_, epb, epp]| (epb.as_str(), epp.as_str())).collect();
+        let mut epb_epp: Vec<_> = values.iter().map(|[_, epb, epp]| (epb, epp)).collect();
         epb_epp.sort_unstable();
         epb_epp.dedup();
-        if epb_epp.is_empty() || (epb_epp.len() == 1 && epb_epp[0] == (DOT, DOT)) {
+        if epb_epp.is_empty() || (epb_epp.len() == 1 && epb_epp[0] == (&Cell::Null, &Cell::Null)) {
             log::trace!("cpu format epb_epp none 2");
             None
         } else {
-            let mut tab = Table::new(&["CPU", "EP bias", "EP preference"]);
-            if epb_epp.len() == 1 {
-                let (epb, epp) = epb_epp[0];
-                tab.row(["all".to_string(), epb.to_string(), epp.to_string()]);
-            } else {
-                tab.rows(values);
-            }
+            let mut tab = Table::new("cpu_epb_epp", EPB_EPP);
+            tab.rows(values);
+            tab.collapse(&[]);
             let r = Some(tab.into());
             log::trace!("cpu format epb_epp done");
             r
@@ -148,36 +154,28 @@ async fn epb_epp(system: PstateSystem, pstates: Vec<PstatePolicy>) -> Option<Str
     }
 }
 
-async fn epps(system: PstateSystem, pstates: Vec<PstatePolicy>) -> Option<String> {
+async fn epps(system: PstateSystem, pstates: Vec<PstatePolicy>) -> Option<Section> {
     log::trace!("cpu format epps start");
     if pstates.is_empty() || !system.is_active().await.ok().unwrap_or(false) {
         log::trace!("cpu format epps none");
         None
     } else {
         let values = join_all(pstates.into_iter().map(|v| async move {
-            let id = v.id().to_string();
-            let prefs = v
-                .energy_performance_available_preferences()
-                .await
-                .ok()
-                .map(|p| p.join(" "))
-                .unwrap_or_else(dot);
+            let id = Cell::from(v.id());
+            let prefs = v.energy_performance_available_preferences().await.ok().into();
             [id, prefs]
         }))
         .await;
-        let mut epps: Vec<_> = values.iter().map(|[_, epps]| epps.as_str()).collect();
+        let mut epps: Vec<_> = values.iter().map(|[_, epps]| epps).collect();
         epps.sort_unstable();
         epps.dedup();
-        if epps.is_empty() || (epps.len() == 1 && epps[0] == DOT) {
+        if epps.is_empty() || (epps.len() == 1 && epps[0] == &Cell::Null) {
             log::trace!("cpu format epps none 2");
             None
         } else {
-            let mut tab = Table::new(&["CPU", "Available EP preferences"]);
-            if epps.len() == 1 {
-                tab.row(["all".to_string(), epps[0].to_string()]);
-            } else {
-                tab.rows(values);
-            }
+            let mut tab = Table::new("cpu_epps", EPPS);
+            tab.rows(values);
+            tab.collapse(&[]);
             let r = Some(tab.into());
             log::trace!("cpu format epps done");
             r
@@ -185,10 +183,20 @@ async fn epps(system: PstateSystem, pstates: Vec<PstatePolicy>) -> Option<String
     }
 }
 
-pub(super) async fn format() -> Vec<Formatter> {
+pub(super) async fn format(ids: Option<Vec<Id>>) -> Vec<Formatter> {
     log::trace!("cpu format start");
     let mut formatters = vec![];
-    let ids: Vec<_> = once::cpu_ids().await;
+    let ids: Vec<_> = if let Some(ids) = ids {
+        let mut ids: Vec<_> = ids
+            .into_iter()
+            .filter_map(|v| if let Id::Cpu(v) = v { Some(v) } else { None })
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    } else {
+        once::cpu_ids().await
+    };
     if !ids.is_empty() {
         let cpus: Vec<_> = ids.clone().into_iter().map(Cpu::new).collect();
         let cpufreqs: Vec<_> = ids.clone().into_iter().map(Cpufreq::new).collect();
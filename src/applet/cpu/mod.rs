@@ -2,12 +2,15 @@ mod args;
 mod format;
 mod run;
 
+use std::collections::HashMap;
+
 use async_trait::async_trait;
-use futures::future::FutureExt as _;
+use futures::future::{FutureExt as _, TryFutureExt as _};
 use measurements::Frequency;
 
 use crate::app::{Arg, Parser};
-use crate::applet::{Applet, Formatter, Runner};
+pub(crate) use crate::applet::cpu::run::{set_offline, set_online, wait_for_onoff};
+use crate::applet::{Applet, Formatter, Id, Journal, Runner};
 use crate::Result;
 
 #[derive(Debug)]
@@ -15,8 +18,8 @@ struct Values {
     ids: Option<Vec<u64>>,
     on: Option<bool>,
     gov: Option<String>,
-    min: Option<Frequency>,
-    max: Option<Frequency>,
+    min: Option<HashMap<u64, Frequency>>,
+    max: Option<HashMap<u64, Frequency>>,
     epb: Option<u64>,
     epp: Option<String>,
 }
@@ -42,13 +45,18 @@ impl Applet for Cpu {
         args::args()
     }
 
-    async fn run(&self, p: Parser<'_>) -> Result<Runner> {
+    fn dry_run(&self) -> bool {
+        true
+    }
+
+    async fn run(&self, p: Parser<'_>, journal: Journal) -> Result<(Runner, Option<Vec<Id>>)> {
         let values = Values::from_parser(p).await?;
-        let r = run::run(values).boxed();
-        Ok(r)
+        let ids = values.ids.as_ref().map(|v| v.iter().cloned().map(Id::Cpu).collect());
+        let r = run::run(values, journal).map_ok(|_| vec![]).boxed();
+        Ok((r, ids))
     }
 
-    async fn format(&self) -> Vec<Formatter> {
-        format::format().await
+    async fn format(&self, ids: Option<Vec<Id>>) -> Vec<Formatter> {
+        format::format(ids).await
     }
 }
@@ -1,11 +1,13 @@
 use std::time::Duration;
 
 use futures::stream::TryStreamExt as _;
+use measurements::Frequency;
 use tokio::time::sleep;
 
+use crate::applet::{Journal, Knob};
 use crate::Result;
 
-async fn wait_for_onoff() {
+pub(crate) async fn wait_for_onoff() {
     let millis = 300;
     log::trace!("wait {}ms for cpu online/offline", millis);
     sleep(Duration::from_millis(millis)).await;
@@ -17,7 +19,7 @@ async fn wait_for_policy() {
     sleep(Duration::from_millis(millis)).await;
 }
 
-async fn set_online(ids: Vec<u64>) -> Result<Vec<u64>> {
+pub(crate) async fn set_online(ids: Vec<u64>) -> Result<Vec<u64>> {
     log::trace!("ensure cpus are online start");
     let mut onlined = vec![];
     if !ids.is_empty() {
@@ -33,7 +35,7 @@ async fn set_online(ids: Vec<u64>) -> Result<Vec<u64>> {
     Ok(onlined)
 }
 
-async fn set_offline(ids: Vec<u64>) -> Result<Vec<u64>> {
+pub(crate) async fn set_offline(ids: Vec<u64>) -> Result<Vec<u64>> {
     log::trace!("ensure cpus are offline start");
     let mut offlined = vec![];
     if !ids.is_empty() {
@@ -59,47 +61,60 @@ impl super::Values {
     }
 }
 
-pub(super) async fn run(values: super::Values) -> Result<()> {
+pub(super) async fn run(values: super::Values, journal: Journal) -> Result<()> {
     log::trace!("cpu run start");
     let has_policy_values = values.has_policy_values();
     if let Some(ids) = values.ids {
         if !ids.is_empty() {
             if has_policy_values {
-                let onlined = set_online(ids.clone()).await?;
+                let onlined =
+                    if journal.is_dry_run() { vec![] } else { set_online(ids.clone()).await? };
                 if !onlined.is_empty() {
                     wait_for_onoff().await;
                 }
-                let min = values.min.map(|v| v.as_kilohertz().trunc() as u64);
-                let max = values.max.map(|v| v.as_kilohertz().trunc() as u64);
+                let khz = |v: Option<&Frequency>| v.map(|v| v.as_kilohertz().round() as u64);
                 log::trace!("cpu run policy start");
-                for id in ids.clone() {
-                    if let Some(v) = values.gov.as_ref() {
-                        syx::cpufreq::set_scaling_governor(id, v).await?;
-                    }
-                    if let Some(v) = min {
-                        syx::cpufreq::set_scaling_min_freq(id, v).await?;
-                    }
-                    if let Some(v) = max {
-                        syx::cpufreq::set_scaling_max_freq(id, v).await?;
-                    }
-                    if let Some(v) = values.epb {
-                        syx::intel_pstate::policy::set_energy_perf_bias(id, v).await?;
-                    }
-                    if let Some(v) = values.epp.as_ref() {
-                        syx::intel_pstate::policy::set_energy_performance_preference(id, v).await?;
+                // Cpus which were onlined are offlined again even when a write
+                // fails, so that a rollback finds them as they were.
+                let r: Result<()> = async {
+                    for id in ids.clone() {
+                        let min = khz(values.min.as_ref().and_then(|v| v.get(&id)));
+                        let max = khz(values.max.as_ref().and_then(|v| v.get(&id)));
+                        if let Some(v) = values.gov.as_deref() {
+                            journal.write(Knob::CpuGovernor(id), v).await?;
+                        }
+                        if let Some(v) = min {
+                            journal.write(Knob::CpuMinFreq(id), v).await?;
+                        }
+                        if let Some(v) = max {
+                            journal.write(Knob::CpuMaxFreq(id), v).await?;
+                        }
+                        if let Some(v) = values.epb {
+                            journal.write(Knob::CpuEpb(id), v).await?;
+                        }
+                        if let Some(v) = values.epp.as_deref() {
+                            journal.write(Knob::CpuEpp(id), v).await?;
+                        }
                     }
+                    Ok(())
                 }
+                .await;
                 log::trace!("cpu run policy done");
                 wait_for_policy().await;
-                if !onlined.is_empty() {
-                    set_offline(onlined).await?;
+                let offlined = if onlined.is_empty() {
+                    Ok(())
+                } else {
+                    let offlined = set_offline(onlined).await.map(drop);
                     wait_for_onoff().await;
-                }
+                    offlined
+                };
+                // A failed write is reported before a failed offline.
+                r.and(offlined)?;
             }
             if let Some(on) = values.on {
                 log::trace!("cpu run online start");
                 for id in ids {
-                    syx::cpu::set_online(id, on).await?;
+                    journal.write(Knob::CpuOnline(id), on).await?;
                 }
                 log::trace!("cpu run online done");
                 wait_for_onoff().await;
@@ -0,0 +1,75 @@
+use crate::app::{Arg, Parser};
+
+const LISTEN: &str = "listen";
+const SOCKET: &str = "socket";
+const TEXTFILE: &str = "textfile";
+
+const LISTEN_SHORT: char = 'l';
+const SOCKET_SHORT: char = 's';
+const TEXTFILE_SHORT: char = 't';
+
+const LISTEN_HELP: &str = "Serve metrics on a tcp address";
+const SOCKET_HELP: &str = "Serve metrics on a unix socket";
+const TEXTFILE_HELP: &str = "Write metrics to a file and exit";
+
+#[rustfmt::skip]
+fn listen_help_long() -> String {
+    format!(
+"Serve metrics over http on a tcp address
+Default: {}",
+    super::run::LISTEN_DEFAULT)
+}
+
+fn socket_help_long() -> String {
+    "Serve metrics over http on a unix socket".to_string()
+}
+
+#[rustfmt::skip]
+fn textfile_help_long() -> String {
+"Write metrics to a file and exit, e.g. for the
+node_exporter textfile collector".to_string()
+}
+
+pub(super) fn args() -> Vec<Arg> {
+    vec![
+        Arg {
+            name: LISTEN.into(),
+            long: LISTEN.into(),
+            short: LISTEN_SHORT.into(),
+            value_name: "ADDR".into(),
+            help: LISTEN_HELP.into(),
+            help_long: listen_help_long().into(),
+            conflicts: vec![SOCKET, TEXTFILE].into(),
+            ..Default::default()
+        },
+        Arg {
+            name: SOCKET.into(),
+            long: SOCKET.into(),
+            short: SOCKET_SHORT.into(),
+            value_name: "PATH".into(),
+            help: SOCKET_HELP.into(),
+            help_long: socket_help_long().into(),
+            conflicts: vec![TEXTFILE].into(),
+            ..Default::default()
+        },
+        Arg {
+            name: TEXTFILE.into(),
+            long: TEXTFILE.into(),
+            short: TEXTFILE_SHORT.into(),
+            value_name: "PATH".into(),
+            help: TEXTFILE_HELP.into(),
+            help_long: textfile_help_long().into(),
+            ..Default::default()
+        },
+    ]
+}
+
+impl super::Values {
+    pub(super) fn from_parser(p: Parser<'_>) -> Self {
+        Self {
+            listen: p.string(LISTEN),
+            socket: p.string(SOCKET),
+            textfile: p.string(TEXTFILE),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::os::unix::fs::FileTypeExt as _;
+
+use tokio::fs::{metadata, remove_file, rename, write};
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _};
+use tokio::net::{TcpListener, UnixListener};
+
+use crate::util::format::{render, Format};
+use crate::{applet, Error, Result};
+
+pub(super) const LISTEN_DEFAULT: &str = "127.0.0.1:9977";
+
+async fn metrics() -> String {
+    log::trace!("exporter metrics start");
+    let applets = applet::all();
+    let sections = applet::format(applets.iter().map(|a| (a.as_ref(), None))).await;
+    let r = render(Format::Prometheus, &sections);
+    log::trace!("exporter metrics done");
+    r
+}
+
+async fn respond<S>(mut stream: S) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // Read the request head only, requests with a body are not served.
+    let mut buf = vec![0; 4 * 1024];
+    let mut len = 0;
+    while len < buf.len() {
+        let n = stream.read(&mut buf[len..]).await?;
+        if n == 0 {
+            break;
+        }
+        len += n;
+        if buf[..len].windows(4).any(|v| v == b"\r\n\r\n") {
+            break;
+        }
+    }
+    let head = String::from_utf8_lossy(&buf[..len]);
+    let mut line = head.lines().next().unwrap_or_default().split_whitespace();
+    let (status, body) = match (line.next(), line.next()) {
+        (Some("GET"), Some("/" | "/metrics")) => ("200 OK", metrics().await),
+        (Some("GET"), _) => ("404 Not Found", "not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "method not allowed\n".to_string()),
+    };
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: \
+         {}\r\nConnection: close\r\n\r\n",
+        status,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+async fn serve_tcp(addr: &str) -> Result<()> {
+    log::trace!("exporter serve tcp start");
+    let listener = TcpListener::bind(addr).await.map_err(|e| Error::io(e, addr))?;
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(async move {
+                    if let Err(e) = respond(stream).await {
+                        log::error!("exporter: {}", e);
+                    }
+                });
+            },
+            Err(e) => log::error!("exporter: {}", e),
+        }
+    }
+}
+
+async fn serve_unix(path: &str) -> Result<()> {
+    log::trace!("exporter serve unix start");
+    // Replace a socket left behind by a previous run, but nothing else.
+    if let Ok(v) = metadata(path).await {
+        if v.file_type().is_socket() {
+            remove_file(path).await.map_err(|e| Error::io(e, path))?;
+        }
+    }
+    let listener = UnixListener::bind(path).map_err(|e| Error::io(e, path))?;
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(async move {
+                    if let Err(e) = respond(stream).await {
+                        log::error!("exporter: {}", e);
+                    }
+                });
+            },
+            Err(e) => log::error!("exporter: {}", e),
+        }
+    }
+}
+
+async fn write_textfile(path: &str) -> Result<()> {
+    log::trace!("exporter write textfile start");
+    // Write then rename, so that collectors never read a partial file.
+    let tmp = format!("{}.tmp", path);
+    write(&tmp, metrics().await).await.map_err(|e| Error::io(e, &tmp))?;
+    rename(&tmp, path).await.map_err(|e| Error::io(e, path))?;
+    log::trace!("exporter write textfile done");
+    Ok(())
+}
+
+pub(super) async fn run(values: super::Values) -> Result<()> {
+    log::trace!("exporter run start");
+    if let Some(path) = values.textfile {
+        write_textfile(&path).await?;
+    } else if let Some(path) = values.socket {
+        serve_unix(&path).await?;
+    } else {
+        let addr = values.listen.unwrap_or_else(|| LISTEN_DEFAULT.to_string());
+        serve_tcp(&addr).await?;
+    }
+    log::trace!("exporter run done");
+    Ok(())
+}
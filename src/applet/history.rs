@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use tokio::fs::{create_dir_all, read_to_string, write};
+
+use crate::applet::knob::{Knob, Value};
+use crate::{Error, Result};
+
+const DIR: &str = "/run/knobs";
+const FILE: &str = "/run/knobs/history.json";
+
+// The number of invocations which may be undone.
+const DEPTH: usize = 8;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct Entry {
+    pub(crate) knob: Knob,
+    pub(crate) old: Value,
+}
+
+async fn read() -> Vec<Vec<Entry>> {
+    match read_to_string(FILE).await {
+        Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+            log::error!("ignoring unreadable history {}: {}", FILE, e);
+            vec![]
+        }),
+        Err(_) => vec![],
+    }
+}
+
+async fn store(history: &[Vec<Entry>]) -> Result<()> {
+    let s = serde_json::to_string(history).map_err(Error::parse_value)?;
+    create_dir_all(DIR).await.map_err(|e| Error::io(e, DIR))?;
+    write(FILE, s).await.map_err(|e| Error::io(e, FILE))
+}
+
+// Returns the values stored by an earlier invocation, `skip` invocations
+// before the latest.
+pub(crate) async fn peek(skip: usize) -> Option<Vec<Entry>> {
+    read().await.into_iter().rev().nth(skip)
+}
+
+// Removes the entries of the latest `undone` invocations, then stores the
+// values replaced by this invocation as a new entry.
+pub(crate) async fn update(undone: usize, entries: Vec<Entry>) -> Result<()> {
+    log::trace!("history update start");
+    let mut history = read().await;
+    history.truncate(history.len().saturating_sub(undone));
+    if !entries.is_empty() {
+        history.push(entries);
+    }
+    let excess = history.len().saturating_sub(DEPTH);
+    history.drain(..excess);
+    store(&history).await?;
+    log::trace!("history update done");
+    Ok(())
+}
@@ -1,5 +1,8 @@
-use crate::app::{Arg, I915Driver, Parser};
-use crate::Result;
+use measurements::Frequency;
+
+use crate::app::{resolve_level, Arg, I915Driver, Parser};
+use crate::applet::Knob;
+use crate::{Error, Result};
 
 const CARD: &str = "card";
 const MIN: &str = "min";
@@ -12,24 +15,48 @@ const MAX_SHORT: char = 'x';
 const BOOST_SHORT: char = 'b';
 
 const CARD_HELP: &str = "Target i915 bus or card ids";
-const MIN_HELP: &str = "Set i915 min freq in megahertz";
-const MAX_HELP: &str = "Set i915 max freq in megahertz";
-const BOOST_HELP: &str = "Set i915 boost freq in megahertz";
+const MIN_HELP: &str = "Set i915 min freq, in megahertz by default";
+const MAX_HELP: &str = "Set i915 max freq, in megahertz by default";
+const BOOST_HELP: &str = "Set i915 boost freq, in megahertz by default";
 
 fn card_help_long() -> String {
     "Target i915 bus or card ids, comma-delimited".to_string()
 }
 
+#[rustfmt::skip]
 fn min_help_long() -> String {
-    format!("Set i915 min freq in megahertz per --{}", CARD)
+    format!(
+"Set i915 min freq per --{}, in megahertz unless
+a unit of Hz, kHz, MHz or GHz is given. Must be
+a whole number of megahertz
+Frequency syntax: 300 0.3GHz 300MHz
+Level syntax: min max 50%
+Adjustment syntax: +100 -100 -10%",
+CARD)
 }
 
+#[rustfmt::skip]
 fn max_help_long() -> String {
-    format!("Set i915 max freq in megahertz per --{}", CARD)
+    format!(
+"Set i915 max freq per --{}, in megahertz unless
+a unit of Hz, kHz, MHz or GHz is given. Must be
+a whole number of megahertz
+Frequency syntax: 1100 1.1GHz 1100MHz
+Level syntax: min max 80%
+Adjustment syntax: +100 -100 -10%",
+CARD)
 }
 
+#[rustfmt::skip]
 fn boost_help_long() -> String {
-    format!("Set i915 boost freq in megahertz per --{}", CARD)
+    format!(
+"Set i915 boost freq per --{}, in megahertz unless
+a unit of Hz, kHz, MHz or GHz is given. Must be
+a whole number of megahertz
+Frequency syntax: 1100 1.1GHz 1100MHz
+Level syntax: min max 80%
+Adjustment syntax: +100 -100 -10%",
+CARD)
 }
 
 pub(super) fn args() -> Vec<Arg> {
@@ -47,43 +74,81 @@ pub(super) fn args() -> Vec<Arg> {
             name: MIN.into(),
             long: MIN.into(),
             short: MIN_SHORT.into(),
-            value_name: "INT".into(),
+            value_name: "FREQ".into(),
             help: MIN_HELP.into(),
             help_long: min_help_long().into(),
             requires: vec![CARD].into(),
+            allow_hyphen_values: true.into(),
             ..Default::default()
         },
         Arg {
             name: MAX.into(),
             long: MAX.into(),
             short: MAX_SHORT.into(),
-            value_name: "INT".into(),
+            value_name: "FREQ".into(),
             help: MAX_HELP.into(),
             help_long: max_help_long().into(),
             requires: vec![CARD].into(),
+            allow_hyphen_values: true.into(),
             ..Default::default()
         },
         Arg {
             name: BOOST.into(),
             long: BOOST.into(),
             short: BOOST_SHORT.into(),
-            value_name: "INT".into(),
+            value_name: "FREQ".into(),
             help: BOOST_HELP.into(),
             help_long: boost_help_long().into(),
             requires: vec![CARD].into(),
+            allow_hyphen_values: true.into(),
             ..Default::default()
         },
     ]
 }
 
+async fn level(id: u64, name: String) -> Result<Option<Frequency>> {
+    let mhz = match name.as_str() {
+        "min" => syx::i915::Values::new(id).rpn_freq_mhz().await,
+        "max" => syx::i915::Values::new(id).rp0_freq_mhz().await,
+        _ => return Ok(None),
+    };
+    let mhz = mhz.map_err(|e| Error::parse_value(format!("i915 {} {}: {}", id, name, e)))?;
+    Ok(Some(Frequency::from_megahertz(mhz as f64)))
+}
+
 impl super::Values {
     pub(super) async fn from_parser(p: Parser<'_>) -> Result<Self> {
         log::trace!("i915 parse start");
+        let ids = p.drm_ids::<I915Driver>(CARD).await?;
+        let min = resolve_level(
+            p.megahertz_level(MIN)?,
+            ids.as_deref(),
+            level,
+            Knob::I915MinFreq,
+        )
+        .await
+        .map_err(|e| Error::parse_flag(e, MIN))?;
+        let max = resolve_level(
+            p.megahertz_level(MAX)?,
+            ids.as_deref(),
+            level,
+            Knob::I915MaxFreq,
+        )
+        .await
+        .map_err(|e| Error::parse_flag(e, MAX))?;
+        let boost = resolve_level(
+            p.megahertz_level(BOOST)?,
+            ids.as_deref(),
+            level,
+            Knob::I915BoostFreq,
+        )
+        .await
+        .map_err(|e| Error::parse_flag(e, BOOST))?;
         let r = Self {
-            ids: p.drm_ids::<I915Driver>(CARD).await?,
-            min: p.megahertz(MIN)?,
-            max: p.megahertz(MAX)?,
-            boost: p.megahertz(BOOST)?,
+            ids,
+            min,
+            max,
+            boost,
         };
         log::trace!("i915 parse done");
         Ok(r)
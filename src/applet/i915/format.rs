@@ -1,18 +1,27 @@
 use futures::future::{join_all, FutureExt as _};
-use measurements::Frequency;
 
-use crate::applet::Formatter;
-use crate::util::format::{dot, frequency, Table};
+use crate::applet::{Formatter, Id};
+use crate::util::format::{Column, Section, Table, Unit};
 use crate::util::once;
 
-fn mhz(v: u64) -> String {
-    frequency(Frequency::from_megahertz(v as f64))
-}
+const TABLE: &[Column] = &[
+    Column::new("i915", "card", Unit::None),
+    Column::new("Bus id", "bus_id", Unit::None),
+    Column::new("Gpu cur", "gpu_cur", Unit::Mhz),
+    Column::new("Gpu min", "gpu_min", Unit::Mhz),
+    Column::new("Gpu max", "gpu_max", Unit::Mhz),
+    Column::new("Gpu boost", "gpu_boost", Unit::Mhz),
+    Column::new("Min lim", "min_lim", Unit::Mhz),
+    Column::new("Max lim", "max_lim", Unit::Mhz),
+];
 
-async fn table() -> Option<String> {
+async fn table(ids: Option<Vec<Id>>) -> Option<Section> {
     log::trace!("i915 format table start");
-    let drm_cards = once::drm_cards().await;
-    let cards: Vec<_> = join_all(drm_cards.into_iter().map(|drm_card| async move {
+    let drm_cards = once::drm_cards()
+        .await
+        .into_iter()
+        .filter(|v| ids.as_ref().map(|ids| ids.contains(&Id::Drm(v.id()))).unwrap_or(true));
+    let cards: Vec<_> = join_all(drm_cards.map(|drm_card| async move {
         let is_i915 = drm_card.driver().await.ok().map(|v| v == "i915").unwrap_or(false);
         if is_i915 {
             let id = drm_card.id();
@@ -31,27 +40,18 @@ async fn table() -> Option<String> {
     } else {
         let rows = join_all(cards.into_iter().map(|(drm_card, card)| async move {
             [
-                drm_card.id().to_string(),
-                drm_card.bus_id().await.ok().map(|v| v.id).unwrap_or_else(dot),
-                card.act_freq_mhz().await.ok().map(mhz).unwrap_or_else(dot),
-                card.min_freq_mhz().await.ok().map(mhz).unwrap_or_else(dot),
-                card.max_freq_mhz().await.ok().map(mhz).unwrap_or_else(dot),
-                card.boost_freq_mhz().await.ok().map(mhz).unwrap_or_else(dot),
-                card.rpn_freq_mhz().await.ok().map(mhz).unwrap_or_else(dot),
-                card.rp0_freq_mhz().await.ok().map(mhz).unwrap_or_else(dot),
+                drm_card.id().into(),
+                drm_card.bus_id().await.ok().map(|v| v.id).into(),
+                card.act_freq_mhz().await.ok().into(),
+                card.min_freq_mhz().await.ok().into(),
+                card.max_freq_mhz().await.ok().into(),
+                card.boost_freq_mhz().await.ok().into(),
+                card.rpn_freq_mhz().await.ok().into(),
+                card.rp0_freq_mhz().await.ok().into(),
             ]
         }))
         .await;
-        let mut tab = Table::new(&[
-            "i915",
-            "Bus id",
-            "Gpu cur",
-            "Gpu min",
-            "Gpu max",
-            "Gpu boost",
-            "Min lim",
-            "Max lim",
-        ]);
+        let mut tab = Table::new("i915", TABLE);
         tab.rows(rows);
         let r = Some(tab.into());
         log::trace!("i915 format table done");
@@ -59,9 +59,9 @@ async fn table() -> Option<String> {
     }
 }
 
-pub(super) async fn format() -> Vec<Formatter> {
+pub(super) async fn format(ids: Option<Vec<Id>>) -> Vec<Formatter> {
     log::trace!("i915 format start");
-    let formatters = vec![table().boxed()];
+    let formatters = vec![table(ids).boxed()];
     log::trace!("i915 format done");
     formatters
 }
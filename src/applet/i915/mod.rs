@@ -2,20 +2,22 @@ mod args;
 mod format;
 mod run;
 
+use std::collections::HashMap;
+
 use async_trait::async_trait;
-use futures::future::FutureExt as _;
+use futures::future::{FutureExt as _, TryFutureExt as _};
 use measurements::Frequency;
 
 use crate::app::{Arg, Parser};
-use crate::applet::{Applet, Formatter, Runner};
+use crate::applet::{Applet, Formatter, Id, Journal, Runner};
 use crate::Result;
 
 #[derive(Debug)]
 struct Values {
     ids: Option<Vec<u64>>,
-    min: Option<Frequency>,
-    max: Option<Frequency>,
-    boost: Option<Frequency>,
+    min: Option<HashMap<u64, Frequency>>,
+    max: Option<HashMap<u64, Frequency>>,
+    boost: Option<HashMap<u64, Frequency>>,
 }
 
 #[derive(Debug, Default)]
@@ -39,13 +41,18 @@ impl Applet for I915 {
         args::args()
     }
 
-    async fn run(&self, p: Parser<'_>) -> Result<Runner> {
+    fn dry_run(&self) -> bool {
+        true
+    }
+
+    async fn run(&self, p: Parser<'_>, journal: Journal) -> Result<(Runner, Option<Vec<Id>>)> {
         let values = Values::from_parser(p).await?;
-        let r = run::run(values).boxed();
-        Ok(r)
+        let ids = values.ids.as_ref().map(|v| v.iter().cloned().map(Id::Drm).collect());
+        let r = run::run(values, journal).map_ok(|_| vec![]).boxed();
+        Ok((r, ids))
     }
 
-    async fn format(&self) -> Vec<Formatter> {
-        format::format().await
+    async fn format(&self, ids: Option<Vec<Id>>) -> Vec<Formatter> {
+        format::format(ids).await
     }
 }
@@ -1,20 +1,24 @@
+use measurements::Frequency;
+
+use crate::applet::{Journal, Knob};
 use crate::Result;
 
-pub(super) async fn run(values: super::Values) -> Result<()> {
+pub(super) async fn run(values: super::Values, journal: Journal) -> Result<()> {
     log::trace!("i915 run start");
     if let Some(cards) = values.ids {
-        let min = values.min.map(|v| v.as_megahertz().trunc() as u64);
-        let max = values.max.map(|v| v.as_megahertz().trunc() as u64);
-        let boost = values.boost.map(|v| v.as_megahertz().trunc() as u64);
+        let mhz = |v: Option<&Frequency>| v.map(|v| v.as_megahertz().round() as u64);
         for id in cards {
+            let min = mhz(values.min.as_ref().and_then(|v| v.get(&id)));
+            let max = mhz(values.max.as_ref().and_then(|v| v.get(&id)));
+            let boost = mhz(values.boost.as_ref().and_then(|v| v.get(&id)));
             if let Some(v) = min {
-                syx::i915::set_min_freq_mhz(id, v).await?;
+                journal.write(Knob::I915MinFreq(id), v).await?;
             }
             if let Some(v) = max {
-                syx::i915::set_max_freq_mhz(id, v).await?;
+                journal.write(Knob::I915MaxFreq(id), v).await?;
             }
             if let Some(v) = boost {
-                syx::i915::set_boost_freq_mhz(id, v).await?;
+                journal.write(Knob::I915BoostFreq(id), v).await?;
             }
         }
     }
@@ -2,10 +2,10 @@ mod args;
 mod run;
 
 use async_trait::async_trait;
-use futures::future::FutureExt as _;
+use futures::future::{FutureExt as _, TryFutureExt as _};
 
 use crate::app::{Arg, Parser};
-use crate::applet::{Applet, Formatter, Runner};
+use crate::applet::{Applet, Formatter, Id, Journal, Runner};
 use crate::Result;
 
 #[derive(Debug)]
@@ -35,13 +35,13 @@ impl Applet for Install {
         args::args()
     }
 
-    async fn run(&self, p: Parser<'_>) -> Result<Runner> {
+    async fn run(&self, p: Parser<'_>, _: Journal) -> Result<(Runner, Option<Vec<Id>>)> {
         let values = Values::from_parser(p);
-        let r = run::run(values).boxed();
-        Ok(r)
+        let r = run::run(values).map_ok(|_| vec![]).boxed();
+        Ok((r, None))
     }
 
-    async fn format(&self) -> Vec<Formatter> {
+    async fn format(&self, _: Option<Vec<Id>>) -> Vec<Formatter> {
         vec![]
     }
 }
@@ -0,0 +1,281 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::applet::cpu::{set_offline, set_online, wait_for_onoff};
+use crate::applet::history::{self, Entry};
+use crate::applet::knob::{Knob, Value};
+use crate::util::format::{Column, Section, Table, Unit};
+use crate::Result;
+
+const CHANGES: &[Column] = &[
+    Column::new("Device", "device", Unit::None),
+    Column::new("Attribute", "attribute", Unit::None),
+    Column::new("Old", "old", Unit::None),
+    Column::new("Requested", "requested", Unit::None),
+    Column::new("New", "new", Unit::None),
+    Column::new("Status", "status", Unit::None),
+];
+
+const PLANNED: &[Column] = &[
+    Column::new("Device", "device", Unit::None),
+    Column::new("Attribute", "attribute", Unit::None),
+    Column::new("Current", "current", Unit::None),
+    Column::new("New", "new", Unit::None),
+];
+
+#[derive(Clone, Debug)]
+pub(crate) struct Change {
+    pub(crate) knob: Knob,
+    pub(crate) old: Option<Value>,
+    pub(crate) requested: Value,
+    pub(crate) new: Option<Value>,
+}
+
+impl Change {
+    // `None` when the requested value cannot be compared with the value read back.
+    fn status(&self) -> Option<&'static str> {
+        match (&self.requested, &self.new) {
+            (Value::Range(..) | Value::Default, _) => None,
+            (_, None) => Some("unread"),
+            (requested, Some(new)) if requested == new => Some("ok"),
+            (requested, Some(new)) if is_quantized(self.knob, requested, new) => Some("quantized"),
+            _ => Some("differs"),
+        }
+    }
+
+    // Returns the expected value when the value read before the write differs
+    // from it. That is the value read back after an applied write of the same
+    // value, or the requested value when there was none. Values which cannot
+    // be read or compared are not drift.
+    fn drift(&self, applied: &[Change]) -> Option<Value> {
+        let applied = applied
+            .iter()
+            .rev()
+            .find(|v| v.knob == self.knob && v.requested == self.requested)
+            .and_then(|v| v.new.as_ref());
+        match (&self.requested, self.old.as_ref()?, applied) {
+            (Value::Range(..) | Value::Default, ..) => None,
+            (_, old, Some(applied)) if old != applied => Some(applied.clone()),
+            (requested, old, None)
+                if requested != old && !is_quantized(self.knob, requested, old) =>
+            {
+                Some(requested.clone())
+            },
+            _ => None,
+        }
+    }
+}
+
+// True when a value read back is the requested value as stored by the
+// kernel. Rapl windows are stored as 2^Y * (1 + Z/4) time units, which is
+// within a quarter of the requested window.
+fn is_quantized(knob: Knob, requested: &Value, read: &Value) -> bool {
+    match (knob, requested, read) {
+        (Knob::RaplWindow(..), Value::Int(requested), Value::Int(read)) => {
+            requested.max(read) - requested.min(read) <= requested / 4
+        },
+        _ => false,
+    }
+}
+
+// Returns the value which undoes a write. Locked gpu clocks cannot be read, so
+// they are reset.
+fn undo_value(knob: Knob, old: Option<Value>) -> Option<Value> {
+    match (knob, old) {
+        (Knob::NvmlGfxFreq(_), None) => Some(Value::Default),
+        (_, old) => old,
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Journal {
+    changes: Arc<Mutex<Vec<Change>>>,
+    dry_run: Arc<AtomicBool>,
+    unrecorded: Arc<AtomicBool>,
+    // The values replaced by this invocation, stored in the undo history once
+    // the invocation ends.
+    recorded: Arc<Mutex<Vec<Entry>>>,
+    // The number of invocations undone by this invocation.
+    undone: Arc<AtomicUsize>,
+}
+
+impl Journal {
+    // Records planned writes instead of writing. Shared by all clones, so that
+    // it may be set after runners are made, but before they run.
+    pub(crate) fn set_dry_run(&self) {
+        self.dry_run.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_dry_run(&self) -> bool {
+        self.dry_run.load(Ordering::Relaxed)
+    }
+
+    // Writes without storing the values replaced in the undo history, e.g.
+    // when the daemon reapplies a profile which drifted.
+    pub(crate) fn set_unrecorded(&self) {
+        self.unrecorded.store(true, Ordering::Relaxed);
+    }
+
+    // Records the value read before the write, and read back after. The value
+    // read before is also recorded for the undo history.
+    pub(crate) async fn write(&self, knob: Knob, value: impl Into<Value>) -> Result<()> {
+        let record = !self.unrecorded.load(Ordering::Relaxed);
+        self.write_knob(knob, value.into(), record).await
+    }
+
+    // Writes values from the undo history, without storing them there again.
+    // Values are restored in the reverse order of their writes. Cpus which are
+    // offline are onlined while their policy values are restored, as when
+    // written.
+    pub(crate) async fn restore(&self, entries: Vec<Entry>) -> Result<()> {
+        log::trace!("journal restore start");
+        let (online, policy): (Vec<_>, Vec<_>) =
+            entries.into_iter().rev().partition(|v| matches!(v.knob, Knob::CpuOnline(_)));
+        for v in online {
+            self.write_knob(v.knob, v.old, false).await?;
+        }
+        let ids: Vec<_> = policy
+            .iter()
+            .filter_map(|v| match v.knob {
+                Knob::CpuGovernor(id)
+                | Knob::CpuMinFreq(id)
+                | Knob::CpuMaxFreq(id)
+                | Knob::CpuEpb(id)
+                | Knob::CpuEpp(id) => Some(id),
+                _ => None,
+            })
+            .collect();
+        let onlined = if self.is_dry_run() { vec![] } else { set_online(ids).await? };
+        if !onlined.is_empty() {
+            wait_for_onoff().await;
+        }
+        for v in policy {
+            self.write_knob(v.knob, v.old, false).await?;
+        }
+        if !onlined.is_empty() {
+            set_offline(onlined).await?;
+            wait_for_onoff().await;
+        }
+        log::trace!("journal restore done");
+        Ok(())
+    }
+
+    // Returns the values replaced by the next invocation to undo, skipping
+    // those already undone by this invocation.
+    pub(crate) async fn undo_entries(&self) -> Option<Vec<Entry>> {
+        history::peek(self.undone.load(Ordering::Relaxed)).await
+    }
+
+    // Marks the entries last returned by `undo_entries` as restored, so that
+    // they are removed from the undo history when this invocation ends.
+    pub(crate) fn set_undone(&self) {
+        self.undone.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Restores the values replaced by the writes of this invocation, so that
+    // neither they nor any undo are stored in the undo history.
+    pub(crate) async fn rollback(&self) -> Result<()> {
+        log::trace!("journal rollback start");
+        let entries = self
+            .changes()
+            .into_iter()
+            .filter_map(|v| undo_value(v.knob, v.old).map(|old| Entry { knob: v.knob, old }))
+            .collect();
+        self.restore(entries).await?;
+        self.recorded.lock().expect("journal lock").clear();
+        self.undone.store(0, Ordering::Relaxed);
+        log::trace!("journal rollback done");
+        Ok(())
+    }
+
+    // Updates the undo history with the values replaced and undone by this
+    // invocation, once its runners are joined.
+    pub(crate) async fn store_history(&self) -> Result<()> {
+        let entries = std::mem::take(&mut *self.recorded.lock().expect("journal lock"));
+        let undone = self.undone.swap(0, Ordering::Relaxed);
+        if self.is_dry_run() || (entries.is_empty() && 0 == undone) {
+            return Ok(());
+        }
+        history::update(undone, entries).await
+    }
+
+    async fn write_knob(&self, knob: Knob, requested: Value, record: bool) -> Result<()> {
+        log::trace!("journal write {} {}", knob.device(), knob.attribute());
+        let old = knob.read().await;
+        if self.is_dry_run() {
+            self.push(Change {
+                knob,
+                old,
+                requested,
+                new: None,
+            });
+            return Ok(());
+        }
+        if record {
+            if let Some(old) = undo_value(knob, old.clone()) {
+                self.recorded.lock().expect("journal lock").push(Entry { knob, old });
+            }
+        }
+        knob.write(&requested).await?;
+        let new = knob.read().await;
+        let change = Change {
+            knob,
+            old,
+            requested,
+            new,
+        };
+        self.push(change);
+        Ok(())
+    }
+
+    fn push(&self, change: Change) {
+        self.changes.lock().expect("journal lock").push(change);
+    }
+
+    pub(crate) fn changes(&self) -> Vec<Change> {
+        self.changes.lock().expect("journal lock").clone()
+    }
+
+    // Returns the changes whose value read differs from the value in effect
+    // after `applied`, e.g. the planned writes of a dry run which are not yet
+    // in effect. Values adjusted by the kernel, driver or firmware when they
+    // were applied are not drift.
+    pub(crate) fn drift(&self, applied: &[Change]) -> Vec<(Change, Value)> {
+        self.changes()
+            .into_iter()
+            .filter_map(|v| v.drift(applied).map(|expected| (v, expected)))
+            .collect()
+    }
+
+    pub(crate) fn format(&self) -> Option<Section> {
+        let changes = self.changes();
+        if changes.is_empty() {
+            return None;
+        }
+        if self.is_dry_run() {
+            let mut tab = Table::new("planned", PLANNED);
+            tab.rows(changes.into_iter().map(|v| {
+                vec![
+                    v.knob.device().into(),
+                    v.knob.attribute().into(),
+                    v.old.into(),
+                    v.requested.into(),
+                ]
+            }));
+            return Some(tab.into());
+        }
+        let mut tab = Table::new("changes", CHANGES);
+        tab.rows(changes.into_iter().map(|v| {
+            let status = v.status();
+            vec![
+                v.knob.device().into(),
+                v.knob.attribute().into(),
+                v.old.into(),
+                v.requested.into(),
+                v.new.into(),
+                status.into(),
+            ]
+        }));
+        Some(tab.into())
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::format::Cell;
+use crate::{Error, Result};
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Value {
+    Bool(bool),
+    Int(u64),
+    Str(String),
+    Range(u64, u64),
+    Default,
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bool(v) => write!(f, "{}", v),
+            Self::Int(v) => write!(f, "{}", v),
+            Self::Str(v) => write!(f, "{}", v),
+            Self::Range(min, max) => write!(f, "{}..{}", min, max),
+            Self::Default => write!(f, "default"),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Self::Bool(v)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(v: u32) -> Self {
+        Self::Int(v.into())
+    }
+}
+
+impl From<u64> for Value {
+    fn from(v: u64) -> Self {
+        Self::Int(v)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Self::Str(v.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Self::Str(v)
+    }
+}
+
+impl From<Value> for Cell {
+    fn from(v: Value) -> Self {
+        match v {
+            Value::Bool(v) => Cell::Bool(v),
+            Value::Int(v) => Cell::Int(v),
+            Value::Str(v) => Cell::Str(v),
+            v => Cell::Str(v.to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Knob {
+    CpuOnline(u64),
+    CpuGovernor(u64),
+    CpuMinFreq(u64),
+    CpuMaxFreq(u64),
+    CpuEpb(u64),
+    CpuEpp(u64),
+    RaplLimit(u64, Option<u64>, u64),
+    RaplWindow(u64, Option<u64>, u64),
+    I915MinFreq(u64),
+    I915MaxFreq(u64),
+    I915BoostFreq(u64),
+    NvmlGfxFreq(u64),
+    NvmlPowerLimit(u64),
+}
+
+impl Knob {
+    pub(crate) fn device(&self) -> String {
+        match *self {
+            Self::CpuOnline(id)
+            | Self::CpuGovernor(id)
+            | Self::CpuMinFreq(id)
+            | Self::CpuMaxFreq(id)
+            | Self::CpuEpb(id)
+            | Self::CpuEpp(id) => format!("cpu {}", id),
+            Self::RaplLimit(package, subzone, constraint)
+            | Self::RaplWindow(package, subzone, constraint) => {
+                if let Some(subzone) = subzone {
+                    format!("rapl {}:{} constraint {}", package, subzone, constraint)
+                } else {
+                    format!("rapl {} constraint {}", package, constraint)
+                }
+            },
+            Self::I915MinFreq(id) | Self::I915MaxFreq(id) | Self::I915BoostFreq(id) => {
+                format!("i915 {}", id)
+            },
+            Self::NvmlGfxFreq(id) | Self::NvmlPowerLimit(id) => format!("nvml {}", id),
+        }
+    }
+
+    pub(crate) fn attribute(&self) -> &'static str {
+        match self {
+            Self::CpuOnline(_) => "online",
+            Self::CpuGovernor(_) => "scaling_governor",
+            Self::CpuMinFreq(_) => "scaling_min_freq",
+            Self::CpuMaxFreq(_) => "scaling_max_freq",
+            Self::CpuEpb(_) => "energy_perf_bias",
+            Self::CpuEpp(_) => "energy_performance_preference",
+            Self::RaplLimit(..) => "power_limit_uw",
+            Self::RaplWindow(..) => "time_window_us",
+            Self::I915MinFreq(_) => "min_freq_mhz",
+            Self::I915MaxFreq(_) => "max_freq_mhz",
+            Self::I915BoostFreq(_) => "boost_freq_mhz",
+            Self::NvmlGfxFreq(_) => "gpu_locked_clocks",
+            Self::NvmlPowerLimit(_) => "power_limit",
+        }
+    }
+
+    // Nvml does not report locked gpu clocks, so that knob always reads `None`.
+    pub(crate) async fn read(&self) -> Option<Value> {
+        match *self {
+            Self::CpuOnline(id) => syx::cpu::Values::new(id).online().await.ok().map(Into::into),
+            Self::CpuGovernor(id) => {
+                syx::cpufreq::Values::new(id).scaling_governor().await.ok().map(Into::into)
+            },
+            Self::CpuMinFreq(id) => {
+                syx::cpufreq::Values::new(id).scaling_min_freq().await.ok().map(Into::into)
+            },
+            Self::CpuMaxFreq(id) => {
+                syx::cpufreq::Values::new(id).scaling_max_freq().await.ok().map(Into::into)
+            },
+            Self::CpuEpb(id) => syx::intel_pstate::policy::Values::new(id)
+                .energy_perf_bias()
+                .await
+                .ok()
+                .map(Into::into),
+            Self::CpuEpp(id) => syx::intel_pstate::policy::Values::new(id)
+                .energy_performance_preference()
+                .await
+                .ok()
+                .map(Into::into),
+            Self::RaplLimit(package, subzone, constraint) => {
+                syx::intel_rapl::constraint::power_limit_uw((package, subzone, constraint))
+                    .await
+                    .ok()
+                    .map(Into::into)
+            },
+            Self::RaplWindow(package, subzone, constraint) => {
+                syx::intel_rapl::constraint::time_window_us((package, subzone, constraint))
+                    .await
+                    .ok()
+                    .map(Into::into)
+            },
+            Self::I915MinFreq(id) => {
+                syx::i915::Values::new(id).min_freq_mhz().await.ok().map(Into::into)
+            },
+            Self::I915MaxFreq(id) => {
+                syx::i915::Values::new(id).max_freq_mhz().await.ok().map(Into::into)
+            },
+            Self::I915BoostFreq(id) => {
+                syx::i915::Values::new(id).boost_freq_mhz().await.ok().map(Into::into)
+            },
+            Self::NvmlGfxFreq(_) => None,
+            Self::NvmlPowerLimit(id) => {
+                syx::nvml::Values::new(id).power_limit().await.ok().map(Into::into)
+            },
+        }
+    }
+
+    pub(crate) async fn write(&self, value: &Value) -> Result<()> {
+        fn u32(v: u64) -> Result<u32> {
+            v.try_into()
+                .map_err(|_| Error::parse_value(format!("value out of range: {}", v)))
+        }
+
+        match (*self, value) {
+            (Self::CpuOnline(id), Value::Bool(v)) => syx::cpu::set_online(id, *v).await?,
+            (Self::CpuGovernor(id), Value::Str(v)) => {
+                syx::cpufreq::set_scaling_governor(id, v).await?
+            },
+            (Self::CpuMinFreq(id), Value::Int(v)) => {
+                syx::cpufreq::set_scaling_min_freq(id, *v).await?
+            },
+            (Self::CpuMaxFreq(id), Value::Int(v)) => {
+                syx::cpufreq::set_scaling_max_freq(id, *v).await?
+            },
+            (Self::CpuEpb(id), Value::Int(v)) => {
+                syx::intel_pstate::policy::set_energy_perf_bias(id, *v).await?
+            },
+            (Self::CpuEpp(id), Value::Str(v)) => {
+                syx::intel_pstate::policy::set_energy_performance_preference(id, v).await?
+            },
+            (Self::RaplLimit(package, subzone, constraint), Value::Int(v)) => {
+                let id = (package, subzone, constraint);
+                syx::intel_rapl::constraint::set_power_limit_uw(id, *v).await?
+            },
+            (Self::RaplWindow(package, subzone, constraint), Value::Int(v)) => {
+                let id = (package, subzone, constraint);
+                syx::intel_rapl::constraint::set_time_window_us(id, *v).await?
+            },
+            (Self::I915MinFreq(id), Value::Int(v)) => syx::i915::set_min_freq_mhz(id, *v).await?,
+            (Self::I915MaxFreq(id), Value::Int(v)) => syx::i915::set_max_freq_mhz(id, *v).await?,
+            (Self::I915BoostFreq(id), Value::Int(v)) => {
+                syx::i915::set_boost_freq_mhz(id, *v).await?
+            },
+            (Self::NvmlGfxFreq(id), Value::Range(min, max)) => {
+                syx::nvml::set_gfx_freq(id, u32(*min)?, u32(*max)?).await?
+            },
+            (Self::NvmlGfxFreq(id), Value::Default) => syx::nvml::reset_gfx_freq(id).await?,
+            (Self::NvmlPowerLimit(id), Value::Int(v)) => {
+                syx::nvml::set_power_limit(id, u32(*v)?).await?
+            },
+            (Self::NvmlPowerLimit(id), Value::Default) => syx::nvml::reset_power_limit(id).await?,
+            (knob, value) => {
+                return Err(Error::parse_value(format!(
+                    "{} {}: invalid value {:?}",
+                    knob.device(),
+                    knob.attribute(),
+                    value
+                )));
+            },
+        }
+        Ok(())
+    }
+}
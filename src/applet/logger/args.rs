@@ -0,0 +1,78 @@
+use crate::app::{Arg, Parser};
+use crate::Result;
+
+const OUTPUT: &str = "output";
+const INTERVAL: &str = "interval";
+const JSON: &str = "json";
+
+const OUTPUT_SHORT: char = 'o';
+const INTERVAL_SHORT: char = 'i';
+const JSON_SHORT: char = 'j';
+
+const OUTPUT_HELP: &str = "Append samples to a file";
+const INTERVAL_HELP: &str = "Set sample interval in seconds";
+const JSON_HELP: &str = "Write json lines instead of csv";
+
+#[rustfmt::skip]
+fn output_help_long() -> String {
+"Append samples to a file instead of stdout. The
+csv header is written only when the file is empty".to_string()
+}
+
+#[rustfmt::skip]
+fn interval_help_long() -> String {
+    format!(
+"Set sample interval in seconds, e.g. 0.1
+Default: {}",
+    super::run::INTERVAL_DEFAULT)
+}
+
+#[rustfmt::skip]
+fn json_help_long() -> String {
+"Write one json object per sample, instead of
+one csv row per sample".to_string()
+}
+
+pub(super) fn args() -> Vec<Arg> {
+    vec![
+        Arg {
+            name: OUTPUT.into(),
+            long: OUTPUT.into(),
+            short: OUTPUT_SHORT.into(),
+            value_name: "PATH".into(),
+            help: OUTPUT_HELP.into(),
+            help_long: output_help_long().into(),
+            ..Default::default()
+        },
+        Arg {
+            name: INTERVAL.into(),
+            long: INTERVAL.into(),
+            short: INTERVAL_SHORT.into(),
+            value_name: "SECS".into(),
+            help: INTERVAL_HELP.into(),
+            help_long: interval_help_long().into(),
+            ..Default::default()
+        },
+        Arg {
+            name: JSON.into(),
+            long: JSON.into(),
+            short: JSON_SHORT.into(),
+            help: JSON_HELP.into(),
+            help_long: json_help_long().into(),
+            ..Default::default()
+        },
+    ]
+}
+
+impl super::Values {
+    pub(super) fn from_parser(p: Parser<'_>) -> Result<Self> {
+        log::trace!("log parse start");
+        let r = Self {
+            output: p.string(OUTPUT),
+            interval: p.seconds(INTERVAL)?,
+            json: p.flag(JSON),
+        };
+        log::trace!("log parse done");
+        Ok(r)
+    }
+}
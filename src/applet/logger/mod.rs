@@ -0,0 +1,50 @@
+mod args;
+mod run;
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future::{FutureExt as _, TryFutureExt as _};
+
+use crate::app::{Arg, Parser};
+use crate::applet::{Applet, Formatter, Id, Journal, Runner};
+use crate::Result;
+
+#[derive(Debug)]
+struct Values {
+    output: Option<String>,
+    interval: Option<Duration>,
+    json: Option<()>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Logger;
+
+#[async_trait]
+impl Applet for Logger {
+    fn binary(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn subcommand(&self) -> &'static str {
+        "log"
+    }
+
+    fn about(&self) -> &'static str {
+        "Log power and frequency samples"
+    }
+
+    fn args(&self) -> Vec<Arg> {
+        args::args()
+    }
+
+    async fn run(&self, p: Parser<'_>, _: Journal) -> Result<(Runner, Option<Vec<Id>>)> {
+        let values = Values::from_parser(p)?;
+        let r = run::run(values).map_ok(|_| vec![]).boxed();
+        Ok((r, None))
+    }
+
+    async fn format(&self, _: Option<Vec<Id>>) -> Vec<Formatter> {
+        vec![]
+    }
+}
@@ -0,0 +1,189 @@
+use std::iter;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use futures::future::join_all;
+use futures::stream::TryStreamExt as _;
+use serde_json::{Map, Value};
+use syx::intel_rapl::zone::{self, Id as ZoneId, Values as Zone};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{stdout, AsyncBufReadExt as _, AsyncWrite, AsyncWriteExt as _, BufReader};
+use tokio::time::interval;
+
+use crate::applet::rapl_energy_delta;
+use crate::util::once;
+use crate::{Error, Result};
+
+pub(super) const INTERVAL_DEFAULT: &str = "1";
+
+#[derive(Clone, Copy, Debug)]
+enum Source {
+    Rapl(ZoneId),
+    Cpu(u64),
+    I915(u64),
+    Nvml(u64),
+}
+
+impl Source {
+    fn field(&self) -> String {
+        match self {
+            Self::Rapl(v) => {
+                if let Some(subzone) = v.subzone() {
+                    format!("rapl_{}_{}_usage_uw", v.package(), subzone)
+                } else {
+                    format!("rapl_{}_usage_uw", v.package())
+                }
+            },
+            Self::Cpu(id) => format!("cpu_{}_cur_khz", id),
+            Self::I915(id) => format!("i915_{}_gpu_cur_mhz", id),
+            Self::Nvml(id) => format!("nvml_{}_power_cur_mw", id),
+        }
+    }
+}
+
+async fn sources() -> Vec<Source> {
+    log::trace!("log sources start");
+    let mut zones: Vec<_> = Zone::all().try_collect().await.unwrap_or_default();
+    zones.sort_by_key(|v| v.id());
+    let mut r: Vec<_> = zones.into_iter().map(|v| Source::Rapl(v.id())).collect();
+    r.extend(once::cpu_ids().await.into_iter().map(Source::Cpu));
+    let cards = join_all(once::drm_cards().await.into_iter().map(|v| async move {
+        match v.driver().await.ok().as_deref() {
+            Some("i915") => Some(Source::I915(v.id())),
+            Some("nvidia") => Some(Source::Nvml(v.id())),
+            _ => None,
+        }
+    }))
+    .await;
+    r.extend(cards.into_iter().flatten());
+    log::trace!("log sources done");
+    r
+}
+
+// Energy counter readings of rapl zones, in the same order as the sources.
+type Energy = Vec<Option<(Instant, u64)>>;
+
+async fn energy(sources: &[Source]) -> Energy {
+    join_all(sources.iter().map(|v| async move {
+        if let Source::Rapl(id) = v {
+            zone::energy_uj(*id).await.ok().map(|v| (Instant::now(), v))
+        } else {
+            None
+        }
+    }))
+    .await
+}
+
+async fn sample(sources: &[Source], energy: &mut Energy) -> Vec<Option<u64>> {
+    let next = self::energy(sources).await;
+    let r = join_all(
+        sources.iter().zip(energy.iter().zip(&next)).map(|(v, (a, b))| async move {
+            match v {
+                Source::Rapl(id) => {
+                    let ((ta, a), (tb, b)) = ((*a)?, (*b)?);
+                    let delta = rapl_energy_delta(*id, a, b).await?;
+                    let uw = delta as f64 / (tb - ta).as_secs_f64();
+                    Some(uw.trunc() as u64)
+                },
+                Source::Cpu(id) => syx::cpufreq::Values::new(*id).scaling_cur_freq().await.ok(),
+                Source::I915(id) => syx::i915::Values::new(*id).act_freq_mhz().await.ok(),
+                Source::Nvml(id) => syx::nvml::Values::new(*id).power().await.ok().map(Into::into),
+            }
+        }),
+    )
+    .await;
+    *energy = next;
+    r
+}
+
+fn timestamp() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|v| v.as_secs_f64())
+        .unwrap_or_default()
+}
+
+fn csv(fields: impl IntoIterator<Item = String>) -> String {
+    let mut r = fields.into_iter().collect::<Vec<_>>().join(",");
+    r.push('\n');
+    r
+}
+
+fn json(fields: &[String], time: f64, values: &[Option<u64>]) -> String {
+    let mut obj = Map::new();
+    obj.insert("time".to_string(), Value::from(time));
+    for (field, v) in fields.iter().zip(values) {
+        obj.insert(field.clone(), v.map(Value::from).unwrap_or(Value::Null));
+    }
+    let mut r = Value::Object(obj).to_string();
+    r.push('\n');
+    r
+}
+
+// Errors when a file holds samples of other fields or in another format, so
+// that appended rows do not land under the wrong columns.
+async fn check_fields(path: &str, json: bool, fields: &[String]) -> Result<()> {
+    let f = File::open(path).await.map_err(|e| Error::io(e, path))?;
+    let mut line = String::new();
+    BufReader::new(f).read_line(&mut line).await.map_err(|e| Error::io(e, path))?;
+    let mut expected: Vec<_> = iter::once("time".to_string()).chain(fields.to_vec()).collect();
+    let matches = if json {
+        let mut keys: Vec<_> = serde_json::from_str::<Map<String, Value>>(line.trim_end())
+            .map(|v| v.keys().cloned().collect())
+            .unwrap_or_default();
+        keys.sort_unstable();
+        expected.sort_unstable();
+        keys == expected
+    } else {
+        line.trim_end() == expected.join(",")
+    };
+    if !matches {
+        let e = format!("{}: holds samples of other fields or format", path);
+        return Err(Error::parse_value(e));
+    }
+    Ok(())
+}
+
+pub(super) async fn run(values: super::Values) -> Result<()> {
+    log::trace!("log run start");
+    let default = Duration::from_secs(INTERVAL_DEFAULT.parse().expect("interval default"));
+    let period = values.interval.unwrap_or(default).max(Duration::from_millis(1));
+    let sources = sources().await;
+    let fields: Vec<_> = sources.iter().map(Source::field).collect();
+    let (mut w, empty): (Box<dyn AsyncWrite + Send + Unpin>, bool) =
+        if let Some(path) = values.output.as_deref() {
+            let f = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await
+                .map_err(|e| Error::io(e, path))?;
+            let empty = f.metadata().await.map(|v| v.len() == 0).unwrap_or(true);
+            if !empty {
+                check_fields(path, values.json.is_some(), &fields).await?;
+            }
+            (Box::new(f), empty)
+        } else {
+            (Box::new(stdout()), true)
+        };
+    if values.json.is_none() && empty {
+        let header = csv(iter::once("time".to_string()).chain(fields.clone()));
+        w.write_all(header.as_bytes()).await?;
+    }
+    let mut ticker = interval(period);
+    ticker.tick().await;
+    let mut energy = energy(&sources).await;
+    loop {
+        ticker.tick().await;
+        let time = timestamp();
+        let samples = sample(&sources, &mut energy).await;
+        let line = if values.json.is_some() {
+            json(&fields, time, &samples)
+        } else {
+            let samples = samples.iter().map(|v| v.map(|v| v.to_string()).unwrap_or_default());
+            csv(iter::once(format!("{:.3}", time)).chain(samples))
+        };
+        // Flush each sample, so that nothing is lost when interrupted.
+        w.write_all(line.as_bytes()).await?;
+        w.flush().await?;
+    }
+}
@@ -1,20 +1,39 @@
 mod cpu;
+mod exporter;
+mod history;
 mod i915;
 mod install;
+mod journal;
+mod knob;
+mod logger;
 mod nvml;
 mod rapl;
+mod save;
+mod undo;
 
 use std::pin::Pin;
 
 use async_trait::async_trait;
-use futures::future::Future;
+use futures::future::{join_all, Future};
 
 use crate::app::{Arg, Parser};
 pub(crate) use crate::applet::cpu::Cpu;
+pub(crate) use crate::applet::exporter::Exporter;
 pub(crate) use crate::applet::i915::I915;
 pub(crate) use crate::applet::install::Install;
+pub(crate) use crate::applet::journal::{Change, Journal};
+pub(crate) use crate::applet::knob::{Knob, Value};
+pub(crate) use crate::applet::logger::Logger;
 pub(crate) use crate::applet::nvml::Nvml;
-pub(crate) use crate::applet::rapl::{ConstraintIds as RaplConstraintIds, Rapl};
+pub(crate) use crate::applet::rapl::{
+    energy_delta as rapl_energy_delta,
+    measure_failed as rapl_measure_failed,
+    ConstraintIds as RaplConstraintIds,
+    Rapl,
+};
+pub(crate) use crate::applet::save::Save;
+pub(crate) use crate::applet::undo::Undo;
+use crate::util::format::Section;
 use crate::Result;
 
 pub(crate) fn all() -> Vec<Box<dyn Applet>> {
@@ -23,16 +42,28 @@ pub(crate) fn all() -> Vec<Box<dyn Applet>> {
         Box::new(Rapl::default()),
         Box::new(I915::default()),
         Box::new(Nvml::default()),
+        Box::new(Exporter::default()),
+        Box::new(Logger::default()),
+        Box::new(Save::default()),
+        Box::new(Undo::default()),
         Box::new(Install::default()),
     ]
 }
 
-pub(crate) type Runner = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Id {
+    Cpu(u64),
+    Drm(u64),
+    Rapl(u64, Option<u64>),
+}
+
+// Runners may return reports, which are printed before the tables.
+pub(crate) type Runner = Pin<Box<dyn Future<Output = Result<Vec<Section>>> + Send>>;
 
-pub(crate) type Formatter = Pin<Box<dyn Future<Output = Option<String>> + Send>>;
+pub(crate) type Formatter = Pin<Box<dyn Future<Output = Option<Section>> + Send>>;
 
 #[async_trait]
-pub(crate) trait Applet {
+pub(crate) trait Applet: Send + Sync {
     fn binary(&self) -> Option<&'static str>;
 
     fn subcommand(&self) -> &'static str;
@@ -41,7 +72,31 @@ pub(crate) trait Applet {
 
     fn args(&self) -> Vec<Arg>;
 
-    async fn run(&self, parser: Parser<'_>) -> Result<Runner>;
+    // Returns true if the runner writes values only through the journal, so
+    // that it may run in dry-run mode. Other runners are skipped.
+    fn dry_run(&self) -> bool {
+        false
+    }
+
+    // Returns the runner, and the device ids targeted by the argument group.
+    // Runners write values through the journal.
+    async fn run(&self, parser: Parser<'_>, journal: Journal) -> Result<(Runner, Option<Vec<Id>>)>;
+
+    // Formats all devices when ids are `None`.
+    async fn format(&self, ids: Option<Vec<Id>>) -> Vec<Formatter>;
+}
 
-    async fn format(&self) -> Vec<Formatter>;
+pub(crate) async fn format<'a, I>(applets: I) -> Vec<Section>
+where
+    I: IntoIterator<Item = (&'a dyn Applet, Option<Vec<Id>>)>,
+{
+    let futs = join_all(applets.into_iter().map(|(a, ids)| a.format(ids))).await.into_iter();
+    let tasks = futs.filter(|futs| !futs.is_empty()).map(|futs| tokio::spawn(join_all(futs)));
+    join_all(tasks)
+        .await
+        .into_iter()
+        .map(|r| r.expect("applet format future"))
+        .flatten()
+        .flatten()
+        .collect()
 }
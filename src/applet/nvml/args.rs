@@ -1,5 +1,8 @@
-use crate::app::{Arg, NvmlDriver, Parser};
-use crate::Result;
+use measurements::Power;
+
+use crate::app::{resolve_level, Arg, NvmlDriver, Parser};
+use crate::applet::Knob;
+use crate::{Error, Result};
 
 const CARD: &str = "card";
 const GPU_MIN: &str = "gpu-min";
@@ -16,30 +19,50 @@ const POWER_SHORT: char = 'P';
 const POWER_RESET_SHORT: char = 'R';
 
 const CARD_HELP: &str = "Target nvml bus or card ids";
-const GPU_MIN_HELP: &str = "Set nvml min gpu freq in megahertz";
-const GPU_MAX_HELP: &str = "Set nvml max gpu freq in megahertz";
+const GPU_MIN_HELP: &str = "Set nvml min gpu freq, in megahertz by default";
+const GPU_MAX_HELP: &str = "Set nvml max gpu freq, in megahertz by default";
 const GPU_RESET_HELP: &str = "Reset nvml gpu freq to default";
-const POWER_HELP: &str = "Set nvml device power limit in watts";
+const POWER_HELP: &str = "Set nvml device power limit, in watts by default";
 const POWER_RESET_HELP: &str = "Reset nvml power limit to default";
 
 fn card_help_long() -> String {
     "Target nvml drm or card ids, comma-delimited".to_string()
 }
 
+#[rustfmt::skip]
 fn gpu_min_help_long() -> String {
-    format!("Set nvml min gpu freq in megahertz per --{}", CARD)
+    format!(
+"Set nvml min gpu freq per --{}, in megahertz
+unless a unit of Hz, kHz, MHz or GHz is given.
+Must be a whole number of megahertz
+Frequency syntax: 300 0.3GHz 300MHz",
+CARD)
 }
 
+#[rustfmt::skip]
 fn gpu_max_help_long() -> String {
-    format!("Set nvml max gpu freq in megahertz per --{}", CARD)
+    format!(
+"Set nvml max gpu freq per --{}, in megahertz
+unless a unit of Hz, kHz, MHz or GHz is given.
+Must be a whole number of megahertz
+Frequency syntax: 1800 1.8GHz 1800MHz",
+CARD)
 }
 
 fn gpu_reset_help_long() -> String {
     format!("Reset nvml gpu freq to default per --{}", CARD)
 }
 
+#[rustfmt::skip]
 fn power_help_long() -> String {
-    format!("Set nvml device power limit in watts per --{}", CARD)
+    format!(
+"Set nvml device power limit per --{}, in watts
+unless a unit of uW, mW, W or kW is given. Must
+be a whole number of milliwatts
+Power syntax: 150 150W 150000mW 0.15kW
+Level syntax: min max 75%, of the max power limit
+Adjustment syntax: +10 -10 -10%",
+CARD)
 }
 
 fn power_reset_help_long() -> String {
@@ -61,7 +84,7 @@ pub(super) fn args() -> Vec<Arg> {
             name: GPU_MIN.into(),
             long: GPU_MIN.into(),
             short: GPU_MIN_SHORT.into(),
-            value_name: "INT".into(),
+            value_name: "FREQ".into(),
             help: GPU_MIN_HELP.into(),
             help_long: gpu_min_help_long().into(),
             requires: vec![CARD].into(),
@@ -71,7 +94,7 @@ pub(super) fn args() -> Vec<Arg> {
             name: GPU_MAX.into(),
             long: GPU_MAX.into(),
             short: GPU_MAX_SHORT.into(),
-            value_name: "INT".into(),
+            value_name: "FREQ".into(),
             help: GPU_MAX_HELP.into(),
             help_long: gpu_max_help_long().into(),
             requires: vec![CARD].into(),
@@ -91,10 +114,11 @@ pub(super) fn args() -> Vec<Arg> {
             name: POWER.into(),
             long: POWER.into(),
             short: POWER_SHORT.into(),
-            value_name: "FLOAT".into(),
+            value_name: "POWER".into(),
             help: POWER_HELP.into(),
             help_long: power_help_long().into(),
             requires: vec![CARD].into(),
+            allow_hyphen_values: true.into(),
             ..Default::default()
         },
         Arg {
@@ -110,15 +134,34 @@ pub(super) fn args() -> Vec<Arg> {
     ]
 }
 
+async fn level(id: u64, name: String) -> Result<Option<Power>> {
+    let mw = match name.as_str() {
+        "min" => syx::nvml::Values::new(id).power_min_limit().await,
+        "max" => syx::nvml::Values::new(id).power_max_limit().await,
+        _ => return Ok(None),
+    };
+    let mw = mw.map_err(|e| Error::parse_value(format!("nvml {} {}: {}", id, name, e)))?;
+    Ok(Some(Power::from_milliwatts(mw as f64)))
+}
+
 impl super::Values {
     pub(super) async fn from_parser(p: Parser<'_>) -> Result<Self> {
         log::trace!("nvml parse start");
+        let cards = p.drm_ids::<NvmlDriver>(CARD).await?;
+        let power = resolve_level(
+            p.milliwatts_level(POWER)?,
+            cards.as_deref(),
+            level,
+            Knob::NvmlPowerLimit,
+        )
+        .await
+        .map_err(|e| Error::parse_flag(e, POWER))?;
         let r = Self {
-            cards: p.drm_ids::<NvmlDriver>(CARD).await?,
+            cards,
             gpu_min: p.megahertz(GPU_MIN)?,
             gpu_max: p.megahertz(GPU_MAX)?,
             gpu_reset: p.flag(GPU_RESET),
-            power: p.watts(POWER)?,
+            power,
             power_reset: p.flag(POWER_RESET),
         };
         log::trace!("nvml parse done");
@@ -1,22 +1,27 @@
 use futures::future::{join_all, FutureExt as _};
-use measurements::{Frequency, Power};
 
-use crate::applet::Formatter;
-use crate::util::format::{dot, frequency, power, Table};
+use crate::applet::{Formatter, Id};
+use crate::util::format::{Column, Section, Table, Unit};
 use crate::util::once;
 
-fn mhz(v: u32) -> String {
-    frequency(Frequency::from_megahertz(v as f64))
-}
-
-fn mw(v: u32) -> String {
-    power(Power::from_milliwatts(v as f64))
-}
+const TABLE: &[Column] = &[
+    Column::new("Nvml", "card", Unit::None),
+    Column::new("Bus id", "bus_id", Unit::None),
+    Column::new("Gpu cur", "gpu_cur", Unit::Mhz),
+    Column::new("Gpu lim", "gpu_lim", Unit::Mhz),
+    Column::new("Power cur", "power_cur", Unit::Mw),
+    Column::new("Power lim", "power_lim", Unit::Mw),
+    Column::new("Min lim", "min_lim", Unit::Mw),
+    Column::new("Max lim", "max_lim", Unit::Mw),
+];
 
-async fn table() -> Option<String> {
+async fn table(ids: Option<Vec<Id>>) -> Option<Section> {
     log::trace!("nvml format table start");
-    let cards = once::drm_cards().await;
-    let cards: Vec<_> = join_all(cards.into_iter().map(|drm_card| async move {
+    let cards = once::drm_cards()
+        .await
+        .into_iter()
+        .filter(|v| ids.as_ref().map(|ids| ids.contains(&Id::Drm(v.id()))).unwrap_or(true));
+    let cards: Vec<_> = join_all(cards.map(|drm_card| async move {
         let is_nvml = drm_card.driver().await.ok().map(|v| v == "nvidia").unwrap_or(false);
         if is_nvml {
             let id = drm_card.id();
@@ -35,27 +40,18 @@ async fn table() -> Option<String> {
     } else {
         let rows = join_all(cards.into_iter().map(|(drm_card, card)| async move {
             [
-                drm_card.id().to_string(),
-                drm_card.bus_id().await.ok().map(|v| v.id).unwrap_or_else(dot),
-                card.gfx_freq().await.ok().map(mhz).unwrap_or_else(dot),
-                card.gfx_max_freq().await.ok().map(mhz).unwrap_or_else(dot),
-                card.power().await.ok().map(mw).unwrap_or_else(dot),
-                card.power_limit().await.ok().map(mw).unwrap_or_else(dot),
-                card.power_min_limit().await.ok().map(mw).unwrap_or_else(dot),
-                card.power_max_limit().await.ok().map(mw).unwrap_or_else(dot),
+                drm_card.id().into(),
+                drm_card.bus_id().await.ok().map(|v| v.id).into(),
+                card.gfx_freq().await.ok().into(),
+                card.gfx_max_freq().await.ok().into(),
+                card.power().await.ok().into(),
+                card.power_limit().await.ok().into(),
+                card.power_min_limit().await.ok().into(),
+                card.power_max_limit().await.ok().into(),
             ]
         }))
         .await;
-        let mut tab = Table::new(&[
-            "Nvml",
-            "Bus id",
-            "Gpu cur",
-            "Gpu lim",
-            "Power cur",
-            "Power lim",
-            "Min lim",
-            "Max lim",
-        ]);
+        let mut tab = Table::new("nvml", TABLE);
         tab.rows(rows);
         let r = Some(tab.into());
         log::trace!("nvml format table done");
@@ -63,9 +59,9 @@ async fn table() -> Option<String> {
     }
 }
 
-pub(super) async fn format() -> Vec<Formatter> {
+pub(super) async fn format(ids: Option<Vec<Id>>) -> Vec<Formatter> {
     log::trace!("nvml format start");
-    let formatters = vec![table().boxed()];
+    let formatters = vec![table(ids).boxed()];
     log::trace!("nvml format done");
     formatters
 }
@@ -2,12 +2,14 @@ mod args;
 mod format;
 mod run;
 
+use std::collections::HashMap;
+
 use async_trait::async_trait;
-use futures::future::FutureExt as _;
+use futures::future::{FutureExt as _, TryFutureExt as _};
 use measurements::{Frequency, Power};
 
 use crate::app::{Arg, Parser};
-use crate::applet::{Applet, Formatter, Runner};
+use crate::applet::{Applet, Formatter, Id, Journal, Runner};
 use crate::Result;
 
 #[derive(Debug)]
@@ -16,7 +18,7 @@ struct Values {
     gpu_min: Option<Frequency>,
     gpu_max: Option<Frequency>,
     gpu_reset: Option<()>,
-    power: Option<Power>,
+    power: Option<HashMap<u64, Power>>,
     power_reset: Option<()>,
 }
 
@@ -37,17 +39,22 @@ impl Applet for Nvml {
         args::args()
     }
 
+    fn dry_run(&self) -> bool {
+        true
+    }
+
     fn about(&self) -> &'static str {
         "View or set nvml values"
     }
 
-    async fn run(&self, p: Parser<'_>) -> Result<Runner> {
+    async fn run(&self, p: Parser<'_>, journal: Journal) -> Result<(Runner, Option<Vec<Id>>)> {
         let values = Values::from_parser(p).await?;
-        let r = run::run(values).boxed();
-        Ok(r)
+        let ids = values.cards.as_ref().map(|v| v.iter().cloned().map(Id::Drm).collect());
+        let r = run::run(values, journal).map_ok(|_| vec![]).boxed();
+        Ok((r, ids))
     }
 
-    async fn format(&self) -> Vec<Formatter> {
-        format::format().await
+    async fn format(&self, ids: Option<Vec<Id>>) -> Vec<Formatter> {
+        format::format(ids).await
     }
 }
@@ -1,25 +1,27 @@
+use crate::applet::{Journal, Knob, Value};
 use crate::Result;
 
-pub(super) async fn run(values: super::Values) -> Result<()> {
+pub(super) async fn run(values: super::Values, journal: Journal) -> Result<()> {
     log::trace!("nvml run start");
     if let Some(nvml) = values.cards {
-        let gpu_min = values.gpu_min.map(|v| v.as_megahertz().trunc() as u32);
-        let gpu_max = values.gpu_max.map(|v| v.as_megahertz().trunc() as u32);
-        let power = values.power.map(|v| v.as_milliwatts().trunc() as u32);
+        let gpu_min = values.gpu_min.map(|v| v.as_megahertz().round() as u64);
+        let gpu_max = values.gpu_max.map(|v| v.as_megahertz().round() as u64);
         for id in nvml {
             if let Some(min) = gpu_min {
                 if let Some(max) = gpu_max {
-                    syx::nvml::set_gfx_freq(id, min, max).await?;
+                    journal.write(Knob::NvmlGfxFreq(id), Value::Range(min, max)).await?;
                 }
             }
             if values.gpu_reset.is_some() {
-                syx::nvml::reset_gfx_freq(id).await?;
+                journal.write(Knob::NvmlGfxFreq(id), Value::Default).await?;
             }
-            if let Some(v) = power {
-                syx::nvml::set_power_limit(id, v).await?;
+            // Whole milliwatts, unless scaled from a level.
+            let power = values.power.as_ref().and_then(|v| v.get(&id));
+            if let Some(v) = power.map(|v| v.as_milliwatts().round() as u64) {
+                journal.write(Knob::NvmlPowerLimit(id), v).await?;
             }
             if values.power_reset.is_some() {
-                syx::nvml::reset_power_limit(id).await?;
+                journal.write(Knob::NvmlPowerLimit(id), Value::Default).await?;
             }
         }
     }
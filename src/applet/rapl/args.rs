@@ -1,23 +1,31 @@
-use crate::app::{Arg, Parser};
-use crate::Result;
+use measurements::Power;
+
+use crate::app::{resolve_level, Arg, Parser};
+use crate::applet::Knob;
+use crate::{Error, Result};
 
 const PACKAGE: &str = "package";
 const SUBZONE: &str = "subzone";
 const CONSTRAINT: &str = "constraint";
 const LIMIT: &str = "limit";
 const WINDOW: &str = "window";
+const MEASURE: &str = "measure";
+const COMMAND: &str = "command";
 
 const PACKAGE_SHORT: char = 'p';
 const SUBZONE_SHORT: char = 's';
 const CONSTRAINT_SHORT: char = 'c';
 const LIMIT_SHORT: char = 'l';
 const WINDOW_SHORT: char = 'w';
+const MEASURE_SHORT: char = 'm';
 
 const PACKAGE_HELP: &str = "Target rapl package";
 const SUBZONE_HELP: &str = "Target rapl subzone";
 const CONSTRAINT_HELP: &str = "Target rapl constraints";
-const LIMIT_HELP: &str = "Set rapl power limit in watts";
-const WINDOW_HELP: &str = "Set rapl power window in microseconds";
+const LIMIT_HELP: &str = "Set rapl power limit, in watts by default";
+const WINDOW_HELP: &str = "Set rapl power window, in microseconds by default";
+const MEASURE_HELP: &str = "Measure the energy consumed by a command";
+const COMMAND_HELP: &str = "Command to measure, given after --";
 
 fn constraint_help_long() -> String {
     "Target rapl constraints, comma-delimited".to_string()
@@ -26,19 +34,34 @@ fn constraint_help_long() -> String {
 #[rustfmt::skip]
 fn limit_help_long() -> String {
     format!(
-"Set rapl power limit in watts per
---{}/{}/{}",
+"Set rapl power limit per --{}/{}/{},
+in watts unless a unit of uW, mW, W or kW is
+given. Must be a whole number of microwatts
+Power syntax: 15 15W 15000mW 0.5kW
+Level syntax: max 50%, of max_power_uw
+Adjustment syntax: +5 -5 -10%",
     PACKAGE, SUBZONE, CONSTRAINT)
 }
 
 #[rustfmt::skip]
 fn window_help_long() -> String {
     format!(
-"Set rapl power window in microseconds per
---{}/{}/{}",
+"Set rapl power window per --{}/{}/{},
+in microseconds unless a unit of us, ms or s
+is given. The kernel quantizes windows, so the
+effective window is shown in the changes table
+Time syntax: 976 976us 28ms 1s 2.5s",
     PACKAGE, SUBZONE, CONSTRAINT)
 }
 
+#[rustfmt::skip]
+fn measure_help_long() -> String {
+"Measure the energy consumed by a command given
+after --, per rapl zone and nvml card. Must be
+the last argument group
+Example: krapl --measure -- make -j8".to_string()
+}
+
 pub(super) fn args() -> Vec<Arg> {
     vec![
         Arg {
@@ -70,32 +93,87 @@ pub(super) fn args() -> Vec<Arg> {
             name: LIMIT.into(),
             long: LIMIT.into(),
             short: LIMIT_SHORT.into(),
-            value_name: "FLOAT".into(),
+            value_name: "POWER".into(),
             help: LIMIT_HELP.into(),
             help_long: limit_help_long().into(),
             requires: vec![PACKAGE, CONSTRAINT].into(),
+            allow_hyphen_values: true.into(),
             ..Default::default()
         },
         Arg {
             name: WINDOW.into(),
             long: WINDOW.into(),
             short: WINDOW_SHORT.into(),
-            value_name: "INT".into(),
+            value_name: "TIME".into(),
             help: WINDOW_HELP.into(),
             help_long: window_help_long().into(),
             requires: vec![PACKAGE, CONSTRAINT].into(),
             ..Default::default()
         },
+        Arg {
+            name: MEASURE.into(),
+            long: MEASURE.into(),
+            short: MEASURE_SHORT.into(),
+            help: MEASURE_HELP.into(),
+            help_long: measure_help_long().into(),
+            requires: vec![COMMAND].into(),
+            ..Default::default()
+        },
+        Arg {
+            name: COMMAND.into(),
+            value_name: "CMD".into(),
+            help: COMMAND_HELP.into(),
+            requires: vec![MEASURE].into(),
+            raw: true.into(),
+            ..Default::default()
+        },
     ]
 }
 
+async fn level(
+    package: u64,
+    subzone: Option<u64>,
+    constraint: u64,
+    name: String,
+) -> Result<Option<Power>> {
+    if "max" != name {
+        return Ok(None);
+    }
+    let uw = syx::intel_rapl::constraint::max_power_uw((package, subzone, constraint))
+        .await
+        .ok()
+        .filter(|v| *v > 0)
+        .ok_or_else(|| {
+            Error::parse_value(format!("constraint {} has no max_power_uw", constraint))
+        })?;
+    Ok(Some(Power::from_microwatts(uw as f64)))
+}
+
 impl super::Values {
     pub(super) async fn from_parser(p: Parser<'_>) -> Result<Self> {
         log::trace!("rapl parse start");
+        let constraint_ids = p.rapl_constraint_ids(PACKAGE, SUBZONE, CONSTRAINT).await?;
+        let limit = match constraint_ids.as_ref() {
+            Some(v) => {
+                let (package, subzone) = (v.package, v.subzone);
+                let level = |constraint, name| level(package, subzone, constraint, name);
+                let knob = |constraint| Knob::RaplLimit(package, subzone, constraint);
+                resolve_level(
+                    p.microwatts_level(LIMIT)?,
+                    Some(&v.constraints),
+                    level,
+                    knob,
+                )
+                .await
+                .map_err(|e| Error::parse_flag(e, LIMIT))?
+            },
+            None => None,
+        };
         let r = Self {
-            constraint_ids: p.rapl_constraint_ids(PACKAGE, SUBZONE, CONSTRAINT).await?,
-            limit: p.watts(LIMIT)?,
+            constraint_ids,
+            limit,
             window: p.microseconds(WINDOW)?,
+            measure: p.strings(COMMAND),
         };
         log::trace!("rapl parse done");
         Ok(r)
@@ -0,0 +1,14 @@
+use syx::intel_rapl::zone::{self, Id as ZoneId};
+
+// Returns the energy consumed between two `energy_uj` readings of a zone. The
+// counter wraps to zero after `max_energy_range_uj`, which is read only when
+// the second reading is lower than the first.
+pub(crate) async fn energy_delta(zone: ZoneId, a: u64, b: u64) -> Option<u64> {
+    if a <= b {
+        Some(b - a)
+    } else {
+        let max = zone::max_energy_range_uj(zone).await.ok()?;
+        log::trace!("rapl energy_uj wrapped {:?}", zone);
+        max.checked_sub(a).map(|v| v + b)
+    }
+}
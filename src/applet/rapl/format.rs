@@ -1,23 +1,28 @@
-use std::time::Duration;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use futures::future::{join_all, FutureExt as _};
 use futures::stream::TryStreamExt as _;
-use measurements::Power;
+use once_cell::sync::Lazy;
 use syx::intel_rapl::constraint::{Values as Constraint, LONG_TERM, SHORT_TERM};
 use syx::intel_rapl::zone::{self, Id as ZoneId, Values as Zone};
 use tokio::time::sleep;
 
-use crate::applet::Formatter;
+use crate::applet::rapl::energy_delta;
+use crate::applet::{Formatter, Id};
 use crate::util::env;
-use crate::util::format::{dot, power, Table};
+use crate::util::format::{Column, Section, Table, Unit};
 
-fn uw(v: u64) -> String {
-    power(Power::from_microwatts(v as f64))
-}
-
-fn us(v: u64) -> String {
-    format!("{} μs", v)
-}
+const TABLE: &[Column] = &[
+    Column::new("RAPL", "zone", Unit::None),
+    Column::new("Zone name", "name", Unit::None),
+    Column::new("Long lim", "long_lim", Unit::Uw),
+    Column::new("Short lim", "short_lim", Unit::Uw),
+    Column::new("Long win", "long_win", Unit::Us),
+    Column::new("Short win", "short_win", Unit::Us),
+    Column::new("Usage", "usage", Unit::Uw),
+];
 
 fn zone_id(v: ZoneId) -> String {
     if let Some(subzone) = v.subzone() {
@@ -35,14 +40,30 @@ async fn limit_window(zone: &Zone, constraint: &str) -> (Option<u64>, Option<u64
     }
 }
 
-async fn energy_uj(zone: ZoneId, interval: Duration, scale: f64) -> (ZoneId, Option<u64>) {
+// The previous reading of each zone, reused as the start of the next sample.
+static SAMPLES: Lazy<Mutex<BTreeMap<ZoneId, (Instant, u64)>>> = Lazy::new(Default::default);
+
+async fn energy_uj(zone: ZoneId, interval: Duration) -> (ZoneId, Option<u64>) {
     log::trace!("rapl format energy_uj start {:?}", zone);
-    if let Ok(a) = zone::energy_uj(zone).await {
-        sleep(interval).await;
+    let prev = SAMPLES.lock().expect("rapl samples lock").get(&zone).cloned();
+    let prev = if let Some(v) = prev {
+        Some(v)
+    } else {
+        zone::energy_uj(zone).await.ok().map(|v| (Instant::now(), v))
+    };
+    if let Some((t, a)) = prev {
+        let wait = interval.saturating_sub(t.elapsed());
+        if !wait.is_zero() {
+            sleep(wait).await;
+        }
         if let Ok(b) = zone::energy_uj(zone).await {
-            let v = ((b - a) as f64 * scale).trunc() as u64;
-            log::trace!("rapl format energy_uj done {:?}", zone);
-            return (zone, Some(v));
+            let now = Instant::now();
+            SAMPLES.lock().expect("rapl samples lock").insert(zone, (now, b));
+            if let Some(delta) = energy_delta(zone, a, b).await {
+                let v = (delta as f64 / (now - t).as_secs_f64()).trunc() as u64;
+                log::trace!("rapl format energy_uj done {:?}", zone);
+                return (zone, Some(v));
+            }
         }
     }
     log::trace!("rapl format energy_uj none {:?}", zone);
@@ -54,16 +75,18 @@ async fn energy_ujs(zones: &[Zone]) -> Vec<(ZoneId, Option<u64>)> {
 
     log::trace!("rapl format energy_ujs start");
     let interval = env::parse::<u64>("RAPL_SAMPLE_MS").unwrap_or(SAMPLE_MS).max(1).min(1000);
-    let scale = 1000. / interval as f64;
     let interval = Duration::from_millis(interval);
-    let r = join_all(zones.iter().map(|v| energy_uj(v.id(), interval, scale))).await;
+    let r = join_all(zones.iter().map(|v| energy_uj(v.id(), interval))).await;
     log::trace!("rapl format energy_ujs done");
     r
 }
 
-async fn table() -> Option<String> {
+async fn table(ids: Option<Vec<Id>>) -> Option<Section> {
     log::trace!("rapl format table start");
     let mut zones: Vec<_> = Zone::all().try_collect().await.unwrap_or_default();
+    if let Some(ids) = ids {
+        zones.retain(|v| ids.contains(&Id::Rapl(v.id().package(), v.id().subzone())));
+    }
     if zones.is_empty() {
         log::trace!("rapl format table none");
         None
@@ -76,26 +99,18 @@ async fn table() -> Option<String> {
                 let (long_lim, long_win) = limit_window(&zone, LONG_TERM).await;
                 let (short_lim, short_win) = limit_window(&zone, SHORT_TERM).await;
                 [
-                    zone_id(zone.id()),
-                    zone.name().await.ok().unwrap_or_else(dot),
-                    long_lim.map(uw).unwrap_or_else(dot),
-                    short_lim.map(uw).unwrap_or_else(dot),
-                    long_win.map(us).unwrap_or_else(dot),
-                    short_win.map(us).unwrap_or_else(dot),
-                    energy_uj.map(uw).unwrap_or_else(dot),
+                    zone_id(zone.id()).into(),
+                    zone.name().await.ok().into(),
+                    long_lim.into(),
+                    short_lim.into(),
+                    long_win.into(),
+                    short_win.into(),
+                    energy_uj.into(),
                 ]
             }
         }))
         .await;
-        let mut tab = Table::new(&[
-            "RAPL",
-            "Zone name",
-            "Long lim",
-            "Short lim",
-            "Long win",
-            "Short win",
-            "Usage",
-        ]);
+        let mut tab = Table::new("rapl", TABLE);
         tab.rows(rows);
         let r = Some(tab.into());
         log::trace!("rapl format table done");
@@ -103,9 +118,9 @@ async fn table() -> Option<String> {
     }
 }
 
-pub(super) async fn format() -> Vec<Formatter> {
+pub(super) async fn format(ids: Option<Vec<Id>>) -> Vec<Formatter> {
     log::trace!("rapl format start");
-    let formatters = vec![table().boxed()];
+    let formatters = vec![table(ids).boxed()];
     log::trace!("rapl format done");
     formatters
 }
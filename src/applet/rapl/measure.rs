@@ -0,0 +1,181 @@
+use std::io::{Error as IoError, ErrorKind};
+use std::process::ExitStatus;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use futures::future::join_all;
+use futures::stream::TryStreamExt as _;
+use once_cell::sync::Lazy;
+use syx::intel_rapl::zone::{self, Values as Zone};
+use tokio::process::Command;
+use tokio::signal::ctrl_c;
+use tokio::time::{interval, timeout};
+
+use crate::applet::rapl::energy_delta;
+use crate::util::format::{Column, Section, Table, Unit};
+use crate::util::once;
+use crate::{Error, Result};
+
+const TABLE: &[Column] = &[
+    Column::new("Device", "device", Unit::None),
+    Column::new("Name", "name", Unit::None),
+    Column::new("Energy", "energy", Unit::Uj),
+    Column::new("Avg power", "power", Unit::Uw),
+    Column::new("Time", "time", Unit::Us),
+];
+
+const SAMPLE_MS: u64 = 100;
+
+// How long an interrupted command is given to exit before it is killed.
+const INTERRUPT_MS: u64 = 1000;
+
+// The status of the last measured command which failed, so that it becomes the
+// exit status of knobs once the report is printed.
+static FAILED: Lazy<Mutex<Option<ExitStatus>>> = Lazy::new(Default::default);
+
+pub(crate) fn failed() -> Option<ExitStatus> {
+    *FAILED.lock().expect("rapl measure lock")
+}
+
+// Accumulated energy of each rapl zone and nvml card.
+struct Meter {
+    zones: Vec<Zone>,
+    cards: Vec<u64>,
+    energy_ujs: Vec<Option<u64>>,
+    zone_ujs: Vec<Option<u64>>,
+    card_ujs: Vec<Option<u64>>,
+    sampled: Instant,
+}
+
+impl Meter {
+    async fn new() -> Self {
+        let mut zones: Vec<_> = Zone::all().try_collect().await.unwrap_or_default();
+        zones.sort_by_key(|v| v.id());
+        let cards = join_all(once::drm_cards().await.into_iter().map(|v| async move {
+            let is_nvml = v.driver().await.ok().map(|v| v == "nvidia").unwrap_or(false);
+            if is_nvml { Some(v.id()) } else { None }
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        let energy_ujs = Self::energy_ujs(&zones).await;
+        let zone_ujs = energy_ujs.iter().map(|v| v.map(|_| 0)).collect();
+        let card_ujs = cards.iter().map(|_| Some(0)).collect();
+        Self {
+            zones,
+            cards,
+            energy_ujs,
+            zone_ujs,
+            card_ujs,
+            sampled: Instant::now(),
+        }
+    }
+
+    async fn energy_ujs(zones: &[Zone]) -> Vec<Option<u64>> {
+        join_all(zones.iter().map(|v| async move { zone::energy_uj(v.id()).await.ok() })).await
+    }
+
+    // Rapl counters are read on every sample, so that wraparound is handled
+    // however long the command runs. Nvml power is integrated over time.
+    async fn sample(&mut self) {
+        let energy_ujs = Self::energy_ujs(&self.zones).await;
+        let deltas = join_all(
+            self.zones.iter().zip(self.energy_ujs.iter().zip(&energy_ujs)).map(
+                |(zone, (a, b))| async move {
+                    match (a, b) {
+                        (Some(a), Some(b)) => energy_delta(zone.id(), *a, *b).await,
+                        _ => None,
+                    }
+                },
+            ),
+        )
+        .await;
+        for (uj, delta) in self.zone_ujs.iter_mut().zip(deltas) {
+            *uj = uj.zip(delta).map(|(uj, delta)| uj + delta);
+        }
+        self.energy_ujs = energy_ujs;
+        let powers = join_all(
+            self.cards
+                .iter()
+                .map(|v| async move { syx::nvml::Values::new(*v).power().await.ok() }),
+        )
+        .await;
+        let now = Instant::now();
+        let ms = (now - self.sampled).as_millis() as u64;
+        for (uj, mw) in self.card_ujs.iter_mut().zip(powers) {
+            *uj = uj.zip(mw).map(|(uj, mw)| uj + u64::from(mw) * ms);
+        }
+        self.sampled = now;
+    }
+
+    async fn table(self, time: Duration) -> Option<Table> {
+        if self.zones.is_empty() && self.cards.is_empty() {
+            return None;
+        }
+        let time_us = time.as_micros() as u64;
+        let power = |uj: Option<u64>| uj.map(|v| (v as f64 / time.as_secs_f64()).trunc() as u64);
+        let mut tab = Table::new("rapl_measure", TABLE);
+        for (zone, uj) in self.zones.into_iter().zip(self.zone_ujs) {
+            let id = zone.id();
+            let device = if let Some(subzone) = id.subzone() {
+                format!("rapl {}:{}", id.package(), subzone)
+            } else {
+                format!("rapl {}", id.package())
+            };
+            let name = zone.name().await.ok();
+            tab.row([
+                device.into(),
+                name.into(),
+                uj.into(),
+                power(uj).into(),
+                time_us.into(),
+            ]);
+        }
+        for (card, uj) in self.cards.into_iter().zip(self.card_ujs) {
+            let device = format!("nvml {}", card);
+            tab.row([
+                device.into(),
+                "gpu".into(),
+                uj.into(),
+                power(uj).into(),
+                time_us.into(),
+            ]);
+        }
+        Some(tab)
+    }
+}
+
+pub(super) async fn measure(command: Vec<String>) -> Result<Vec<Section>> {
+    log::trace!("rapl measure start");
+    let (program, args) = command.split_first().expect("measure command");
+    let mut meter = Meter::new().await;
+    let start = Instant::now();
+    let mut child = Command::new(program).args(args).spawn().map_err(|e| Error::io(e, program))?;
+    let mut ticker = interval(Duration::from_millis(SAMPLE_MS));
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => break status.map_err(|e| Error::io(e, program))?,
+            _ = ticker.tick() => meter.sample().await,
+            // The command receives the terminal's SIGINT too. Once it has
+            // exited, the error lets the values of this invocation be restored.
+            _ = ctrl_c() => {
+                let wait = Duration::from_millis(INTERRUPT_MS);
+                if timeout(wait, child.wait()).await.is_err() {
+                    child.kill().await.map_err(|e| Error::io(e, program))?;
+                }
+                let e = IoError::from(ErrorKind::Interrupted);
+                return Err(Error::io(e, program));
+            },
+        }
+    };
+    let time = start.elapsed();
+    meter.sample().await;
+    let mut r: Vec<Section> = meter.table(time).await.into_iter().map(Into::into).collect();
+    r.push(Section::Status("command", status.to_string()));
+    if !status.success() {
+        *FAILED.lock().expect("rapl measure lock") = Some(status);
+    }
+    log::trace!("rapl measure done");
+    Ok(r)
+}
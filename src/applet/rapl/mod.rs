@@ -1,7 +1,10 @@
 mod args;
+mod energy;
 mod format;
+mod measure;
 mod run;
 
+use std::collections::HashMap;
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -9,7 +12,9 @@ use futures::future::FutureExt as _;
 use measurements::Power;
 
 use crate::app::{Arg, Parser};
-use crate::applet::{Applet, Formatter, Runner};
+pub(crate) use crate::applet::rapl::energy::energy_delta;
+pub(crate) use crate::applet::rapl::measure::failed as measure_failed;
+use crate::applet::{Applet, Formatter, Id, Journal, Runner};
 use crate::Result;
 
 #[derive(Debug)]
@@ -22,8 +27,9 @@ pub(crate) struct ConstraintIds {
 #[derive(Debug)]
 struct Values {
     constraint_ids: Option<ConstraintIds>,
-    limit: Option<Power>,
+    limit: Option<HashMap<u64, Power>>,
     window: Option<Duration>,
+    measure: Option<Vec<String>>,
 }
 
 #[derive(Debug, Default)]
@@ -47,13 +53,18 @@ impl Applet for Rapl {
         args::args()
     }
 
-    async fn run(&self, p: Parser<'_>) -> Result<Runner> {
+    fn dry_run(&self) -> bool {
+        true
+    }
+
+    async fn run(&self, p: Parser<'_>, journal: Journal) -> Result<(Runner, Option<Vec<Id>>)> {
         let values = Values::from_parser(p).await?;
-        let r = run::run(values).boxed();
-        Ok(r)
+        let ids = values.constraint_ids.as_ref().map(|v| vec![Id::Rapl(v.package, v.subzone)]);
+        let r = run::run(values, journal).boxed();
+        Ok((r, ids))
     }
 
-    async fn format(&self) -> Vec<Formatter> {
-        format::format().await
+    async fn format(&self, ids: Option<Vec<Id>>) -> Vec<Formatter> {
+        format::format(ids).await
     }
 }
@@ -1,20 +1,28 @@
+use crate::applet::{Journal, Knob};
+use crate::util::format::Section;
 use crate::Result;
 
-pub(super) async fn run(values: super::Values) -> Result<()> {
+pub(super) async fn run(values: super::Values, journal: Journal) -> Result<Vec<Section>> {
     log::trace!("rapl run start");
     if let Some(constraint_ids) = values.constraint_ids {
-        let limit = values.limit.map(|v| v.as_microwatts().trunc() as u64);
-        let window = values.window.map(|v| v.as_micros().try_into().unwrap());
+        let window = values.window.map(|v| u64::try_from(v.as_micros()).unwrap());
         for constraint in constraint_ids.constraints {
-            let id = (constraint_ids.package, constraint_ids.subzone, constraint);
-            if let Some(v) = limit {
-                syx::intel_rapl::constraint::set_power_limit_uw(id, v).await?;
+            let (package, subzone) = (constraint_ids.package, constraint_ids.subzone);
+            // Whole microwatts, unless scaled from a level.
+            let limit = values.limit.as_ref().and_then(|v| v.get(&constraint));
+            if let Some(v) = limit.map(|v| v.as_microwatts().round() as u64) {
+                journal.write(Knob::RaplLimit(package, subzone, constraint), v).await?;
             }
             if let Some(v) = window {
-                syx::intel_rapl::constraint::set_time_window_us(id, v).await?;
+                journal.write(Knob::RaplWindow(package, subzone, constraint), v).await?;
             }
         }
     }
+    let reports = if let Some(command) = values.measure.filter(|_| !journal.is_dry_run()) {
+        super::measure::measure(command).await?
+    } else {
+        vec![]
+    };
     log::trace!("rapl run done");
-    Ok(())
+    Ok(reports)
 }
@@ -0,0 +1,31 @@
+use crate::app::{Arg, Parser};
+
+const FILE: &str = "file";
+
+const FILE_HELP: &str = "Profile file path";
+
+#[rustfmt::skip]
+fn file_help_long() -> String {
+"Profile file path. The file is overwritten with the
+current cpu, rapl, i915 and nvml values, which may be
+applied later with `knobs apply FILE`".to_string()
+}
+
+pub(super) fn args() -> Vec<Arg> {
+    vec![Arg {
+        name: FILE.into(),
+        value_name: "FILE".into(),
+        help: FILE_HELP.into(),
+        help_long: file_help_long().into(),
+        required: true.into(),
+        ..Default::default()
+    }]
+}
+
+impl super::Values {
+    pub(super) fn from_parser(p: Parser<'_>) -> Self {
+        Self {
+            file: p.string(FILE).expect("save file"),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+mod args;
+mod run;
+
+use async_trait::async_trait;
+use futures::future::{FutureExt as _, TryFutureExt as _};
+
+use crate::app::{Arg, Parser};
+use crate::applet::{Applet, Formatter, Id, Journal, Runner};
+use crate::Result;
+
+#[derive(Debug)]
+struct Values {
+    file: String,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Save;
+
+#[async_trait]
+impl Applet for Save {
+    fn binary(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn subcommand(&self) -> &'static str {
+        "save"
+    }
+
+    fn about(&self) -> &'static str {
+        "Save current values as a profile"
+    }
+
+    fn args(&self) -> Vec<Arg> {
+        args::args()
+    }
+
+    async fn run(&self, p: Parser<'_>, _: Journal) -> Result<(Runner, Option<Vec<Id>>)> {
+        let values = Values::from_parser(p);
+        let r = run::run(values).map_ok(|_| vec![]).boxed();
+        Ok((r, None))
+    }
+
+    async fn format(&self, _: Option<Vec<Id>>) -> Vec<Formatter> {
+        vec![]
+    }
+}
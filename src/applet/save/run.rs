@@ -0,0 +1,186 @@
+use std::iter;
+
+use futures::future::join_all;
+use futures::stream::TryStreamExt as _;
+use syx::intel_rapl::zone::Values as Zone;
+use tokio::fs::write;
+use toml::value::{Table, Value as TomlValue};
+
+use crate::applet::{Knob, Value};
+use crate::util::once;
+use crate::{Error, Result};
+
+const HEADER: &str = "# Saved by `knobs save`. Apply with `knobs apply FILE`.
+# Nvml locked gpu clocks cannot be read, so they are not saved.
+";
+
+// Profile keys and values of a device.
+type Entries = Vec<(&'static str, TomlValue)>;
+
+// Returns the profile key and value of a knob, in the units of its flag.
+fn entry(knob: Knob, value: Value) -> Option<(&'static str, TomlValue)> {
+    let int = |v: u64| i64::try_from(v).ok().map(TomlValue::Integer);
+    // Megahertz when whole, so that the profile stays exact.
+    let khz = |v: u64| if 0 == v % 1000 { int(v / 1000) } else { Some(format!("{}kHz", v).into()) };
+    // Watts when whole, for the same reason.
+    let uw = |v: u64| {
+        if 0 == v % 1_000_000 {
+            int(v / 1_000_000)
+        } else {
+            Some(format!("{}uW", v).into())
+        }
+    };
+    let mw = |v: u64| if 0 == v % 1000 { int(v / 1000) } else { Some(format!("{}mW", v).into()) };
+    let r = match (knob, value) {
+        (Knob::CpuOnline(_), Value::Bool(v)) => ("on", TomlValue::Boolean(v)),
+        (Knob::CpuGovernor(_), Value::Str(v)) => ("gov", TomlValue::String(v)),
+        (Knob::CpuMinFreq(_), Value::Int(v)) => ("min", khz(v)?),
+        (Knob::CpuMaxFreq(_), Value::Int(v)) => ("max", khz(v)?),
+        (Knob::CpuEpb(_), Value::Int(v)) => ("epb", int(v)?),
+        (Knob::CpuEpp(_), Value::Str(v)) => ("epp", TomlValue::String(v)),
+        (Knob::RaplLimit(..), Value::Int(v)) => ("limit", uw(v)?),
+        (Knob::RaplWindow(..), Value::Int(v)) => ("window", int(v)?),
+        (Knob::I915MinFreq(_), Value::Int(v)) => ("min", int(v)?),
+        (Knob::I915MaxFreq(_), Value::Int(v)) => ("max", int(v)?),
+        (Knob::I915BoostFreq(_), Value::Int(v)) => ("boost", int(v)?),
+        (Knob::NvmlPowerLimit(_), Value::Int(v)) => ("power", mw(v)?),
+        _ => return None,
+    };
+    Some(r)
+}
+
+async fn entries(knobs: &[Knob]) -> Entries {
+    join_all(knobs.iter().map(|k| async move { k.read().await.and_then(|v| entry(*k, v)) }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+fn table<'a>(entries: impl IntoIterator<Item = (&'a str, TomlValue)>) -> TomlValue {
+    TomlValue::Table(entries.into_iter().map(|(k, v)| (k.to_string(), v)).collect::<Table>())
+}
+
+// Formats cpu ids as a list of ids and inclusive ranges, e.g. `0..3,6`.
+fn cpu_ids(ids: &[u64]) -> String {
+    let mut runs: Vec<(u64, u64)> = vec![];
+    for &id in ids {
+        match runs.last_mut() {
+            Some((_, last)) if *last + 1 == id => *last = id,
+            _ => runs.push((id, id)),
+        }
+    }
+    let mut r = vec![];
+    for (first, last) in runs {
+        if first == last {
+            r.push(first.to_string());
+        } else {
+            r.push(format!("{}..{}", first, last));
+        }
+    }
+    r.join(",")
+}
+
+// Cpus with equal values share an argument group.
+async fn cpu() -> Vec<TomlValue> {
+    let mut groups: Vec<(Vec<u64>, Entries)> = vec![];
+    for id in once::cpu_ids().await {
+        let knobs = [
+            Knob::CpuOnline(id),
+            Knob::CpuGovernor(id),
+            Knob::CpuMinFreq(id),
+            Knob::CpuMaxFreq(id),
+            Knob::CpuEpb(id),
+            Knob::CpuEpp(id),
+        ];
+        let entries = entries(&knobs).await;
+        if entries.is_empty() {
+            continue;
+        }
+        if let Some((ids, _)) = groups.iter_mut().find(|(_, v)| *v == entries) {
+            ids.push(id);
+        } else {
+            groups.push((vec![id], entries));
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(ids, entries)| {
+            let ids = ("cpu", TomlValue::String(cpu_ids(&ids)));
+            table(iter::once(ids).chain(entries))
+        })
+        .collect()
+}
+
+async fn rapl() -> Vec<TomlValue> {
+    let mut zones: Vec<_> = Zone::all().try_collect().await.unwrap_or_default();
+    zones.sort_by_key(|v| v.id());
+    let mut r = vec![];
+    for zone in zones {
+        let (package, subzone) = (zone.id().package(), zone.id().subzone());
+        for constraint in 0.. {
+            let id = (package, subzone, constraint);
+            if !syx::intel_rapl::constraint::exists(id).await.unwrap_or(false) {
+                break;
+            }
+            let knobs = [
+                Knob::RaplLimit(package, subzone, constraint),
+                Knob::RaplWindow(package, subzone, constraint),
+            ];
+            let entries = entries(&knobs).await;
+            if entries.is_empty() {
+                continue;
+            }
+            let int = |v: u64| TomlValue::Integer(v as i64);
+            let ids = iter::once(("package", int(package)))
+                .chain(subzone.map(|v| ("subzone", int(v))))
+                .chain(iter::once(("constraint", int(constraint))));
+            r.push(table(ids.chain(entries)));
+        }
+    }
+    r
+}
+
+async fn drm() -> (Vec<TomlValue>, Vec<TomlValue>) {
+    let (mut i915, mut nvml) = (vec![], vec![]);
+    for card in once::drm_cards().await {
+        let id = card.id();
+        let (groups, knobs) = match card.driver().await.ok().as_deref() {
+            Some("i915") => (&mut i915, vec![
+                Knob::I915MinFreq(id),
+                Knob::I915MaxFreq(id),
+                Knob::I915BoostFreq(id),
+            ]),
+            Some("nvidia") => (&mut nvml, vec![Knob::NvmlPowerLimit(id)]),
+            _ => continue,
+        };
+        let entries = entries(&knobs).await;
+        if !entries.is_empty() {
+            let card = ("card", TomlValue::Integer(id as i64));
+            groups.push(table(iter::once(card).chain(entries)));
+        }
+    }
+    (i915, nvml)
+}
+
+pub(super) async fn run(values: super::Values) -> Result<()> {
+    log::trace!("save run start");
+    let (i915, nvml) = drm().await;
+    let groups = [
+        ("cpu", cpu().await),
+        ("rapl", rapl().await),
+        ("i915", i915),
+        ("nvml", nvml),
+    ];
+    let profile = table(
+        groups
+            .into_iter()
+            .filter(|(_, v)| !v.is_empty())
+            .map(|(k, v)| (k, TomlValue::Array(v))),
+    );
+    let mut s = HEADER.to_string();
+    s.push_str(&toml::to_string(&profile).map_err(Error::parse_value)?);
+    write(&values.file, s).await.map_err(|e| Error::io(e, &values.file))?;
+    log::trace!("save run done");
+    Ok(())
+}
@@ -0,0 +1,43 @@
+mod run;
+
+use async_trait::async_trait;
+use futures::future::{FutureExt as _, TryFutureExt as _};
+
+use crate::app::{Arg, Parser};
+use crate::applet::{Applet, Formatter, Id, Journal, Runner};
+use crate::Result;
+
+#[derive(Debug, Default)]
+pub(crate) struct Undo;
+
+#[async_trait]
+impl Applet for Undo {
+    fn binary(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn subcommand(&self) -> &'static str {
+        "undo"
+    }
+
+    fn about(&self) -> &'static str {
+        "Restore the values written by the last invocation"
+    }
+
+    fn args(&self) -> Vec<Arg> {
+        vec![]
+    }
+
+    fn dry_run(&self) -> bool {
+        true
+    }
+
+    async fn run(&self, _: Parser<'_>, journal: Journal) -> Result<(Runner, Option<Vec<Id>>)> {
+        let r = run::run(journal).map_ok(|_| vec![]).boxed();
+        Ok((r, None))
+    }
+
+    async fn format(&self, _: Option<Vec<Id>>) -> Vec<Formatter> {
+        vec![]
+    }
+}
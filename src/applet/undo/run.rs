@@ -0,0 +1,12 @@
+use crate::applet::Journal;
+use crate::{Error, Result};
+
+pub(super) async fn run(journal: Journal) -> Result<()> {
+    log::trace!("undo run start");
+    let entries = journal.undo_entries().await;
+    let entries = entries.ok_or_else(|| Error::parse_value("nothing to undo"))?;
+    journal.restore(entries).await?;
+    journal.set_undone();
+    log::trace!("undo run done");
+    Ok(())
+}
@@ -3,6 +3,7 @@ mod applet;
 mod util;
 
 use std::fmt::Display;
+use std::process::ExitStatus;
 
 pub use clap::Error as ClapError;
 pub use syx::Error as SyxError;
@@ -24,6 +25,9 @@ pub enum Error {
     #[error("{0}")]
     Syx(#[from] SyxError),
 
+    #[error("{0}")]
+    Io(#[from] IoError),
+
     #[error("--{flag}: {error}")]
     ParseFlag { error: String, flag: String },
 
@@ -32,6 +36,21 @@ pub enum Error {
 
     #[error("argument group {1}: {0}")]
     Group(String, usize),
+
+    #[error("profile {1}: {0}")]
+    Profile(String, String),
+
+    #[error("{1} line {2}: {0}")]
+    Line(String, String, usize),
+
+    #[error("{0}\nvalues written by this invocation were restored")]
+    RolledBack(String),
+
+    #[error("{0}\nrestoring values written by this invocation failed: {1}")]
+    Rollback(String, String),
+
+    #[error("command failed: {0}")]
+    Command(ExitStatus),
 }
 
 impl Error {
@@ -46,19 +65,53 @@ impl Error {
         Self::ParseValue(message)
     }
 
-    fn group(error: Self, group: usize) -> Self {
-        if let Error::Clap(err) = &error {
-            if matches!(
+    fn io(error: IoError, context: impl Display) -> Self {
+        let error = IoError::new(error.kind(), format!("{}: {}", context, error));
+        Self::Io(error)
+    }
+
+    fn rolled_back(error: Self) -> Self {
+        Self::RolledBack(error.to_string())
+    }
+
+    fn rollback(error: Self, rollback: Self) -> Self {
+        Self::Rollback(error.to_string(), rollback.to_string())
+    }
+
+    fn is_help(&self) -> bool {
+        if let Error::Clap(err) = self {
+            matches!(
                 err.kind,
                 clap::ErrorKind::DisplayHelp
                     | clap::ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
-            ) {
-                return error;
-            }
+            )
+        } else {
+            false
+        }
+    }
+
+    fn group(error: Self, group: usize) -> Self {
+        if error.is_help() {
+            return error;
         }
         let error = error.to_string();
         Self::Group(error, group)
     }
+
+    fn line(error: Self, path: impl Display, line: usize) -> Self {
+        if error.is_help() {
+            return error;
+        }
+        let error = error.to_string();
+        let path = path.to_string();
+        Self::Line(error, path, line)
+    }
+
+    fn profile(error: Self, context: impl Display) -> Self {
+        let error = error.to_string();
+        let context = context.to_string();
+        Self::Profile(error, context)
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -1,11 +1,14 @@
+use std::iter;
+use std::str::FromStr;
+
 use comfy_table as ct;
 use measurements::{Frequency, Power};
+use serde_json::{Map, Value};
 
-pub(crate) const DOT: &str = "\u{2022}";
+use crate::app::NAME;
+use crate::{Error, Result};
 
-pub(crate) fn dot() -> String {
-    DOT.to_string()
-}
+pub(crate) const DOT: &str = "\u{2022}";
 
 pub(crate) fn frequency(f: Frequency) -> String {
     let h = f.as_hertz().trunc() as u64;
@@ -29,21 +32,276 @@ pub(crate) fn power(p: Power) -> String {
     }
 }
 
-#[derive(Debug)]
-pub(crate) struct Table<'a> {
-    header: &'a [&'static str],
-    rows: Vec<Vec<String>>,
+// Renders microseconds exactly in the largest unit of at least 1, e.g. 28 ms,
+// so that quantized rapl windows can be told from the requested ones.
+pub(crate) fn duration(us: u64) -> String {
+    let (scale, digits, unit) = if us < 1000 {
+        (1, 0, "μs")
+    } else if us < 1_000_000 {
+        (1000, 3, "ms")
+    } else {
+        (1_000_000, 6, "s")
+    };
+    let (int, frac) = (us / scale, us % scale);
+    if 0 == frac {
+        format!("{} {}", int, unit)
+    } else {
+        let frac = format!("{:0digits$}", frac, digits = digits);
+        format!("{}.{} {}", int, frac.trim_end_matches('0'), unit)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Format {
+    Text,
+    Json,
+    Csv,
+    Tsv,
+    Prometheus,
+}
+
+impl Format {
+    // Field delimiter for delimited formats.
+    fn delimiter(&self) -> Option<char> {
+        match self {
+            Self::Csv => Some(','),
+            Self::Tsv => Some('\t'),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self::Text
+    }
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.to_lowercase();
+        match s.as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
+            "prometheus" => Ok(Self::Prometheus),
+            _ => Err(Error::parse_value(format!(
+                "expected text, json, csv, tsv, or prometheus, got {:?}",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Unit {
+    None,
+    Khz,
+    Mhz,
+    Uw,
+    Mw,
+    Uj,
+    Us,
+}
+
+impl Unit {
+    fn suffix(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Khz => Some("khz"),
+            Self::Mhz => Some("mhz"),
+            Self::Uw => Some("uw"),
+            Self::Mw => Some("mw"),
+            Self::Uj => Some("uj"),
+            Self::Us => Some("us"),
+        }
+    }
+
+    // Base unit and scale of raw values, used for metric names and values.
+    fn base(&self) -> Option<(&'static str, f64)> {
+        match self {
+            Self::None => None,
+            Self::Khz => Some(("hertz", 1e3)),
+            Self::Mhz => Some(("hertz", 1e6)),
+            Self::Uw => Some(("watts", 1e-6)),
+            Self::Mw => Some(("watts", 1e-3)),
+            Self::Uj => Some(("joules", 1e-6)),
+            Self::Us => Some(("seconds", 1e-6)),
+        }
+    }
+
+    fn metric(&self, v: u64) -> String {
+        match self.base() {
+            // Divided by the inverse, so that e.g. microseconds print exactly.
+            Some((_, scale)) if scale < 1. => (v as f64 / (1. / scale).round()).to_string(),
+            Some((_, scale)) => (v * scale as u64).to_string(),
+            None => v.to_string(),
+        }
+    }
+
+    fn text(&self, v: u64) -> String {
+        match self {
+            Self::None => v.to_string(),
+            Self::Khz => frequency(Frequency::from_kilohertz(v as f64)),
+            Self::Mhz => frequency(Frequency::from_megahertz(v as f64)),
+            Self::Uw => power(Power::from_microwatts(v as f64)),
+            Self::Mw => power(Power::from_milliwatts(v as f64)),
+            Self::Uj => format!("{:.2} J", v as f64 / 1e6),
+            Self::Us => duration(v),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Column {
+    name: &'static str,
+    key: &'static str,
+    unit: Unit,
+}
+
+impl Column {
+    pub(crate) const fn new(name: &'static str, key: &'static str, unit: Unit) -> Self {
+        Self { name, key, unit }
+    }
+
+    // Machine-readable field name, suffixed with the unit of raw values.
+    fn field(&self) -> String {
+        if let Some(suffix) = self.unit.suffix() {
+            format!("{}_{}", self.key, suffix)
+        } else {
+            self.key.to_string()
+        }
+    }
+
+    // Metric name, suffixed with the base unit of metric values.
+    fn metric(&self, table: &str) -> String {
+        if let Some((base, _)) = self.unit.base() {
+            format!("{}_{}_{}_{}", NAME, table, self.key, base)
+        } else {
+            format!("{}_{}_{}", NAME, table, self.key)
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) enum Cell {
+    Null,
+    Bool(bool),
+    Int(u64),
+    Str(String),
+    List(Vec<String>),
+}
+
+impl Cell {
+    fn text(&self, unit: Unit) -> String {
+        match self {
+            Self::Null => DOT.to_string(),
+            Self::Bool(v) => v.to_string(),
+            Self::Int(v) => unit.text(*v),
+            Self::Str(v) => v.clone(),
+            Self::List(v) => v.join(" "),
+        }
+    }
+
+    fn raw(&self) -> String {
+        match self {
+            Self::Null => String::new(),
+            Self::Bool(v) => v.to_string(),
+            Self::Int(v) => v.to_string(),
+            Self::Str(v) => v.clone(),
+            Self::List(v) => v.join(" "),
+        }
+    }
+
+    fn json(&self) -> Value {
+        match self {
+            Self::Null => Value::Null,
+            Self::Bool(v) => Value::from(*v),
+            Self::Int(v) => Value::from(*v),
+            Self::Str(v) => Value::from(v.as_str()),
+            Self::List(v) => Value::from(v.clone()),
+        }
+    }
+}
+
+impl From<bool> for Cell {
+    fn from(v: bool) -> Self {
+        Self::Bool(v)
+    }
+}
+
+impl From<u32> for Cell {
+    fn from(v: u32) -> Self {
+        Self::Int(v.into())
+    }
+}
+
+impl From<u64> for Cell {
+    fn from(v: u64) -> Self {
+        Self::Int(v)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(v: &str) -> Self {
+        Self::Str(v.to_string())
+    }
+}
+
+impl From<String> for Cell {
+    fn from(v: String) -> Self {
+        Self::Str(v)
+    }
+}
+
+impl From<Vec<String>> for Cell {
+    fn from(v: Vec<String>) -> Self {
+        Self::List(v)
+    }
+}
+
+impl<T> From<Option<T>> for Cell
+where
+    T: Into<Cell>,
+{
+    fn from(v: Option<T>) -> Self {
+        v.map(Into::into).unwrap_or(Self::Null)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Table {
+    name: &'static str,
+    columns: Vec<Column>,
+    rows: Vec<Vec<Cell>>,
+    summarized: Option<&'static [&'static str]>,
 }
 
-impl<'a> Table<'a> {
+impl Table {
     const LINE: &'static str = "-";
 
-    pub(crate) fn new(header: &'a [&'static str]) -> Self {
+    pub(crate) fn new(name: &'static str, columns: &'static [Column]) -> Self {
+        let columns = columns.to_vec();
         let rows = vec![];
-        Self { header, rows }
+        Self {
+            name,
+            columns,
+            rows,
+            summarized: None,
+        }
+    }
+
+    // Collapses runs of rows with consecutive ids and otherwise equal values
+    // into one row in text output. Summarized columns may differ within a run,
+    // and show min/avg/max.
+    pub(crate) fn collapse(&mut self, summarized: &'static [&'static str]) {
+        self.summarized = Some(summarized);
     }
 
-    pub(crate) fn row(&mut self, row: impl IntoIterator<Item = String>) {
+    pub(crate) fn row(&mut self, row: impl IntoIterator<Item = Cell>) {
         let row = row.into_iter().collect();
         self.rows.push(row);
     }
@@ -51,35 +309,319 @@ impl<'a> Table<'a> {
     pub(crate) fn rows<I, R>(&mut self, rows: I)
     where
         I: IntoIterator<Item = R>,
-        R: IntoIterator<Item = String>,
+        R: IntoIterator<Item = Cell>,
     {
         for row in rows {
             self.row(row);
         }
     }
-}
 
-impl<'a> From<Table<'a>> for String {
-    fn from(v: Table<'a>) -> Self {
-        let seps: Vec<_> = v
-            .header
+    // Keeps the table when it is named, with or without its applet prefix, or
+    // when any of its columns are named. When columns are named, only those
+    // and the id column are kept.
+    fn show(mut self, names: &[String]) -> Option<Self> {
+        let named = |v: &str| names.iter().any(|n| n == v);
+        let short = self.name.split_once('_').map(|(_, v)| v);
+        let keep: Vec<_> = self
+            .columns
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, c)| named(c.key))
+            .map(|(i, _)| i)
+            .collect();
+        if !keep.is_empty() {
+            let keep: Vec<_> = iter::once(0).chain(keep).collect();
+            self.columns = keep.iter().map(|&i| self.columns[i]).collect();
+            for row in &mut self.rows {
+                *row = keep.iter().map(|&i| row[i].clone()).collect();
+            }
+            Some(self)
+        } else if named(self.name) || short.map(named).unwrap_or(false) {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    // Groups rows into runs, see `collapse()`.
+    fn runs(&self, summarized: &[usize]) -> Vec<Vec<&Vec<Cell>>> {
+        let mut runs: Vec<Vec<&Vec<Cell>>> = vec![];
+        for row in &self.rows {
+            if let Some(run) = runs.last_mut() {
+                let (first, last) = (run[0], run[run.len() - 1]);
+                let consecutive = match (&last[0], &row[0]) {
+                    (Cell::Int(a), Cell::Int(b)) => a + 1 == *b,
+                    _ => false,
+                };
+                let equal = (1..row.len()).all(|i| summarized.contains(&i) || first[i] == row[i]);
+                if consecutive && equal {
+                    run.push(row);
+                    continue;
+                }
+            }
+            runs.push(vec![row]);
+        }
+        runs
+    }
+
+    fn run_text(&self, run: &[&Vec<Cell>], summarized: &[usize]) -> Vec<String> {
+        let (first, last) = (run[0], run[run.len() - 1]);
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                if i == 0 && run.len() > 1 {
+                    format!("{}-{}", first[0].raw(), last[0].raw())
+                } else if summarized.contains(&i) {
+                    let values: Vec<_> = run
+                        .iter()
+                        .filter_map(|row| if let Cell::Int(v) = row[i] { Some(v) } else { None })
+                        .collect();
+                    let min = values.iter().min();
+                    let max = values.iter().max();
+                    match (min, max) {
+                        (Some(min), Some(max)) if min != max => {
+                            let avg = values.iter().sum::<u64>() / values.len() as u64;
+                            format!(
+                                "{}/{}/{}",
+                                c.unit.text(*min),
+                                c.unit.text(avg),
+                                c.unit.text(*max)
+                            )
+                        },
+                        (Some(v), _) => c.unit.text(*v),
+                        _ => DOT.to_string(),
+                    }
+                } else {
+                    first[i].text(c.unit)
+                }
+            })
+            .collect()
+    }
+
+    fn text(&self) -> String {
+        let rows: Vec<Vec<_>> = if let Some(summarized) = self.summarized {
+            let summarized: Vec<_> = self
+                .columns
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| summarized.contains(&c.key))
+                .map(|(i, _)| i)
+                .collect();
+            let runs = self.runs(&summarized);
+            runs.iter().map(|run| self.run_text(run, &summarized)).collect()
+        } else {
+            self.rows
+                .iter()
+                .map(|row| row.iter().zip(&self.columns).map(|(v, c)| v.text(c.unit)).collect())
+                .collect()
+        };
+        let seps: Vec<_> = self
+            .columns
             .iter()
             .enumerate()
-            .map(|(i, h)| {
-                let h = h.chars().count();
-                let c = v.rows.iter().fold(h, |a, v| v[i].chars().count().max(a));
+            .map(|(i, c)| {
+                let h = c.name.chars().count();
+                let c = rows.iter().fold(h, |a, v| v[i].chars().count().max(a));
                 Table::LINE.repeat(c)
             })
             .collect();
         let mut tab = ct::Table::new();
         tab.load_preset(ct::presets::NOTHING);
-        tab.set_header(v.header);
+        tab.set_header(self.columns.iter().map(|c| c.name));
         tab.add_row(seps);
-        for row in v.rows {
+        for row in rows {
             tab.add_row(row);
         }
         let mut r = tab.to_string();
         r.push('\n');
         r
     }
+
+    fn json(&self) -> Value {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let obj: Map<_, _> =
+                    row.iter().zip(&self.columns).map(|(v, c)| (c.field(), v.json())).collect();
+                Value::Object(obj)
+            })
+            .collect();
+        Value::Array(rows)
+    }
+
+    // Each column becomes a gauge labelled with the first column. String values
+    // become labels on a gauge of 1, and lists are omitted.
+    fn metrics(&self) -> String {
+        let mut r = String::new();
+        if let Some((id, columns)) = self.columns.split_first() {
+            for (i, column) in columns.iter().enumerate() {
+                let name = column.metric(self.name);
+                let samples: Vec<_> = self
+                    .rows
+                    .iter()
+                    .filter_map(|row| {
+                        let label = format!("{}=\"{}\"", id.key, metric_label(&row[0].raw()));
+                        match &row[i + 1] {
+                            Cell::Bool(v) => Some(format!("{}{{{}}} {}", name, label, *v as u8)),
+                            Cell::Int(v) => {
+                                Some(format!("{}{{{}}} {}", name, label, column.unit.metric(*v)))
+                            },
+                            Cell::Str(v) => Some(format!(
+                                "{}{{{},{}=\"{}\"}} 1",
+                                name,
+                                label,
+                                column.key,
+                                metric_label(v)
+                            )),
+                            Cell::Null | Cell::List(_) => None,
+                        }
+                    })
+                    .collect();
+                if !samples.is_empty() {
+                    r.push_str(&format!("# TYPE {} gauge\n", name));
+                    for sample in samples {
+                        r.push_str(&sample);
+                        r.push('\n');
+                    }
+                }
+            }
+        }
+        r
+    }
+
+    fn delimited(&self, delimiter: char) -> String {
+        let header = self.columns.iter().map(|c| c.field());
+        let rows = self.rows.iter().map(|row| row.iter().map(Cell::raw));
+        delimited(delimiter, header, rows)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Section {
+    Table(Table),
+    Status(&'static str, String),
+}
+
+impl Section {
+    pub(crate) fn show(self, names: &[String]) -> Option<Self> {
+        match self {
+            Self::Table(v) => v.show(names).map(Self::Table),
+            Self::Status(name, _) if names.iter().any(|n| n == name) => Some(self),
+            Self::Status(..) => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Table(v) => v.name,
+            Self::Status(name, _) => name,
+        }
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Self::Table(v) => v.text(),
+            Self::Status(name, v) => format!(" {}: {}\n", name, v),
+        }
+    }
+
+    fn json(&self) -> Value {
+        match self {
+            Self::Table(v) => v.json(),
+            Self::Status(_, v) => Value::from(v.as_str()),
+        }
+    }
+
+    fn metrics(&self) -> String {
+        match self {
+            Self::Table(v) => v.metrics(),
+            Self::Status(name, v) => {
+                let name = format!("{}_{}_status", NAME, name);
+                format!(
+                    "# TYPE {} gauge\n{}{{status=\"{}\"}} 1\n",
+                    name,
+                    name,
+                    metric_label(v)
+                )
+            },
+        }
+    }
+
+    fn delimited(&self, delimiter: char) -> String {
+        match self {
+            Self::Table(v) => v.delimited(delimiter),
+            Self::Status(name, v) => delimited(delimiter, [name.to_string()], [[v.clone()]]),
+        }
+    }
+}
+
+impl From<Table> for Section {
+    fn from(v: Table) -> Self {
+        Self::Table(v)
+    }
+}
+
+fn metric_label(v: &str) -> String {
+    v.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn delimited_field(delimiter: char, v: String) -> String {
+    if delimiter == '\t' {
+        v.replace(['\t', '\n'], " ")
+    } else if v.contains([delimiter, '"', '\n']) {
+        format!("\"{}\"", v.replace('"', "\"\""))
+    } else {
+        v
+    }
+}
+
+fn delimited<H, I, R>(delimiter: char, header: H, rows: I) -> String
+where
+    H: IntoIterator<Item = String>,
+    I: IntoIterator<Item = R>,
+    R: IntoIterator<Item = String>,
+{
+    let line = |row: Vec<String>| {
+        let mut r = row.join(&delimiter.to_string());
+        r.push('\n');
+        r
+    };
+    let field = |v| delimited_field(delimiter, v);
+    let mut r = line(header.into_iter().map(field).collect());
+    for row in rows {
+        r.push_str(&line(row.into_iter().map(field).collect()));
+    }
+    r
+}
+
+pub(crate) fn render(format: Format, sections: &[Section]) -> String {
+    match format {
+        Format::Text => sections.iter().map(Section::text).collect::<Vec<_>>().join("\n"),
+        Format::Json => {
+            // Sections of the same name, e.g. of several profiles, are keyed
+            // `name`, `name_2` and so on.
+            let mut obj = Map::new();
+            for v in sections {
+                let mut key = v.name().to_string();
+                for i in 2.. {
+                    if !obj.contains_key(&key) {
+                        break;
+                    }
+                    key = format!("{}_{}", v.name(), i);
+                }
+                obj.insert(key, v.json());
+            }
+            let mut r = serde_json::to_string_pretty(&Value::Object(obj)).expect("json output");
+            r.push('\n');
+            r
+        },
+        Format::Csv | Format::Tsv => {
+            let delimiter = format.delimiter().expect("delimiter");
+            sections.iter().map(|v| v.delimited(delimiter)).collect::<Vec<_>>().join("\n")
+        },
+        Format::Prometheus => sections.iter().map(Section::metrics).collect(),
+    }
 }
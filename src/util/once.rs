@@ -1,27 +1,39 @@
 use futures::stream::TryStreamExt as _;
 use syx::drm::Cache as DrmCard;
-use tokio::sync::OnceCell;
+use tokio::sync::RwLock;
+
+static CPU_IDS: RwLock<Option<Vec<u64>>> = RwLock::const_new(None);
+static DRM_CARDS: RwLock<Option<Vec<DrmCard>>> = RwLock::const_new(None);
 
 pub(crate) async fn cpu_ids() -> Vec<u64> {
-    static CPU_IDS: OnceCell<Vec<u64>> = OnceCell::const_new();
-    CPU_IDS
-        .get_or_init(|| async {
-            let mut v: Vec<_> = syx::cpu::ids().try_collect().await.unwrap_or_default();
-            v.sort_unstable();
-            v
-        })
-        .await
-        .clone()
+    if let Some(v) = CPU_IDS.read().await.as_ref() {
+        return v.clone();
+    }
+    let mut lock = CPU_IDS.write().await;
+    if lock.is_none() {
+        let mut v: Vec<_> = syx::cpu::ids().try_collect().await.unwrap_or_default();
+        v.sort_unstable();
+        *lock = Some(v);
+    }
+    lock.clone().expect("cpu ids")
 }
 
 pub(crate) async fn drm_cards() -> Vec<DrmCard> {
-    static DRM_CARDS: OnceCell<Vec<DrmCard>> = OnceCell::const_new();
-    DRM_CARDS
-        .get_or_init(|| async {
-            let mut v: Vec<_> = DrmCard::all().try_collect().await.unwrap_or_default();
-            v.sort_unstable_by_key(|c| c.id());
-            v
-        })
-        .await
-        .clone()
+    if let Some(v) = DRM_CARDS.read().await.as_ref() {
+        return v.clone();
+    }
+    let mut lock = DRM_CARDS.write().await;
+    if lock.is_none() {
+        let mut v: Vec<_> = DrmCard::all().try_collect().await.unwrap_or_default();
+        v.sort_unstable_by_key(|c| c.id());
+        *lock = Some(v);
+    }
+    lock.clone().expect("drm cards")
+}
+
+// Drops the cached values, so that they are read again on next use.
+pub(crate) async fn refresh() {
+    log::trace!("once refresh");
+    *CPU_IDS.write().await = None;
+    *DRM_CARDS.write().await = None;
 }